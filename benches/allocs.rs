@@ -0,0 +1,183 @@
+//! How many heap allocations each write costs once warmed up, for `Latest`
+//! next to the queues and cells it is compared against, at a few payload
+//! sizes.
+//!
+//! Criterion only measures time, so this counts allocations itself, through a
+//! global allocator that wraps the system one, and prints a table. It is its
+//! own bench binary so the counting allocator slows down nothing else. Each
+//! write is followed by a read on the same thread, so queues stay drained and
+//! the ring, if there is one, keeps cycling through its slots.
+
+use rio_thread_bench::message::{Message, Payload};
+use rio_thread_bench::{channel, SeqLatest};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::mem;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc};
+
+/// Counts every allocation, including reallocations, on its way to `System`.
+struct Counting;
+
+static ALLOCS: AtomicU64 = AtomicU64::new(0);
+
+// SAFETY: every call is passed straight on to `System`.
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// Writes left out of the count, long enough to wrap every ring and queue.
+const WARMUP: u64 = 1_000;
+
+const WRITES: u64 = 100_000;
+
+/// Calls `write_then_read` `WARMUP` times, then `WRITES` times more, and
+/// returns how many allocations the latter made per call.
+fn allocs_per_write(mut write_then_read: impl FnMut(u64)) -> f64 {
+    for i in 0..WARMUP {
+        write_then_read(i);
+    }
+    let before = ALLOCS.load(Ordering::Relaxed);
+    for i in WARMUP..WARMUP + WRITES {
+        write_then_read(i);
+    }
+    (ALLOCS.load(Ordering::Relaxed) - before) as f64 / WRITES as f64
+}
+
+fn msg<M: Payload>(i: u64) -> M {
+    M::new(i as f64, -(i as f64))
+}
+
+/// Returns the allocations per write of each contender, publishing `M`.
+fn contenders<M: Payload>() -> Vec<(&'static str, f64)> {
+    let mut results = Vec::new();
+
+    let (mut writer, reader) = channel::<M>(3);
+    results.push((
+        "latest",
+        allocs_per_write(|i| {
+            writer.set(msg(i));
+            black_box(reader.get());
+        }),
+    ));
+
+    let mut writer = SeqLatest::new(msg::<M>(0));
+    let reader = writer.reader();
+    results.push((
+        "seqlock",
+        allocs_per_write(|i| {
+            writer.set(msg(i));
+            black_box(reader.get());
+        }),
+    ));
+
+    let swap = arc_swap::ArcSwap::from_pointee(msg::<M>(0));
+    results.push((
+        "arcswap",
+        allocs_per_write(|i| {
+            swap.store(Arc::new(msg(i)));
+            black_box(swap.load_full());
+        }),
+    ));
+
+    let (tx, rx) = mpsc::sync_channel::<M>(200);
+    results.push((
+        "mpsc",
+        allocs_per_write(|i| {
+            tx.send(msg(i)).unwrap();
+            black_box(rx.try_recv().ok());
+        }),
+    ));
+
+    // Unbounded channels grow in blocks as they go rather than reusing a
+    // fixed buffer.
+    let (tx, rx) = mpsc::channel::<M>();
+    results.push((
+        "mpsc_unbounded",
+        allocs_per_write(|i| {
+            tx.send(msg(i)).unwrap();
+            black_box(rx.try_recv().ok());
+        }),
+    ));
+
+    #[cfg(feature = "compare-bus")]
+    {
+        let mut bus = bus::Bus::<M>::new(200);
+        let mut rx = bus.add_rx();
+        results.push((
+            "bus",
+            allocs_per_write(|i| {
+                bus.broadcast(msg(i));
+                black_box(rx.try_recv().ok());
+            }),
+        ));
+    }
+
+    #[cfg(feature = "compare-flume")]
+    {
+        let (tx, rx) = flume::bounded::<M>(200);
+        results.push((
+            "flume",
+            allocs_per_write(|i| {
+                tx.send(msg(i)).unwrap();
+                black_box(rx.try_recv().ok());
+            }),
+        ));
+    }
+
+    #[cfg(feature = "compare-crossbeam")]
+    {
+        let (tx, rx) = crossbeam_channel::bounded::<M>(200);
+        results.push((
+            "crossbeam_channel",
+            allocs_per_write(|i| {
+                tx.send(msg(i)).unwrap();
+                black_box(rx.try_recv().ok());
+            }),
+        ));
+    }
+
+    results
+}
+
+fn main() {
+    let sizes = [
+        mem::size_of::<Message<0>>(),
+        mem::size_of::<Message<48>>(),
+        mem::size_of::<Message<240>>(),
+        mem::size_of::<Message<1008>>(),
+    ];
+    let columns = [
+        contenders::<Message<0>>(),
+        contenders::<Message<48>>(),
+        contenders::<Message<240>>(),
+        contenders::<Message<1008>>(),
+    ];
+    print!("{:>18}", "allocs/write");
+    for size in sizes.iter() {
+        print!(" {:>8}", format!("{}B", size));
+    }
+    println!();
+    for (row, &(name, _)) in columns[0].iter().enumerate() {
+        print!("{:>18}", name);
+        for column in columns.iter() {
+            print!(" {:>8.3}", column[row].1);
+        }
+        println!();
+    }
+}
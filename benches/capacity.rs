@@ -0,0 +1,73 @@
+//! `Latest` reads and writes at a few ring capacities, with a slow reader
+//! pinning a slot most of the time, to help pick `cap`.
+//!
+//! The slow reader holds each value it reads for a while before reading the
+//! next, so the writer keeps finding its slot taken and has to skip it. With
+//! 2 slots there is nowhere else to go while it holds one, so writes use
+//! `try_set` and that row mostly measures failing to publish.
+
+mod common;
+
+use crate::common::{contend_every, Contender};
+use criterion::{criterion_group, criterion_main, Criterion};
+use rio_thread_bench::message::{Message, Payload};
+use rio_thread_bench::{channel, LatestReader};
+use std::hint::black_box;
+use std::thread;
+use std::time::Duration;
+
+const CAPACITIES: [usize; 4] = [2, 4, 8, 16];
+
+/// How long the slow reader holds on to each value.
+const HOLD: Duration = Duration::from_micros(50);
+
+/// Spawns a reader that keeps hold of each value it reads for `HOLD`.
+fn slow_reader(reader: &LatestReader<Message>) -> Contender {
+    let reader = reader.clone();
+    contend_every(Duration::ZERO, move |_| {
+        let _held = reader.get_arc();
+        thread::sleep(HOLD);
+    })
+}
+
+fn reads(c: &mut Criterion) {
+    let mut group = c.benchmark_group("capacity/reads");
+    for &cap in CAPACITIES.iter() {
+        group.bench_function(format!("cap-{}", cap), |b| {
+            let (mut writer, reader) = channel::<Message>(cap);
+            let _slow = slow_reader(&reader);
+            let _writer = contend_every(Duration::from_nanos(5), move |i| {
+                let _ = writer.try_set(Message::new(i as f64, -(i as f64)));
+            });
+            b.iter(|| black_box(reader.get().expect("q closed")));
+        });
+    }
+    group.finish();
+}
+
+fn writes(c: &mut Criterion) {
+    let mut group = c.benchmark_group("capacity/writes");
+    for &cap in CAPACITIES.iter() {
+        group.bench_function(format!("cap-{}", cap), |b| {
+            let (mut writer, reader) = channel::<Message>(cap);
+            let _slow = slow_reader(&reader);
+            let _reader = contend_every(Duration::from_nanos(5), move |_| {
+                black_box(reader.get());
+            });
+            let mut i = 0;
+            b.iter(|| {
+                let _ = black_box(writer.try_set(Message::new(i as f64, -(i as f64))));
+                i += 1;
+            });
+        });
+    }
+    group.finish();
+}
+
+fn benches(c: &mut Criterion) {
+    reads(c);
+    writes(c);
+}
+
+criterion_group!(capacity, benches);
+criterion_main!(capacity);
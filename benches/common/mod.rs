@@ -0,0 +1,42 @@
+//! Helpers shared by the benchmarks.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// A background thread calling `f` in a loop until it is dropped.
+pub struct Contender {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+/// Spawns a `Contender` that calls `f` with an increasing counter, sleeping
+/// for `interval` between calls, or spinning without a break if it is zero.
+pub fn contend_every(interval: Duration, mut f: impl FnMut(u64) + Send + 'static) -> Contender {
+    let stop = Arc::new(AtomicBool::new(false));
+    let stopped = stop.clone();
+    let handle = thread::spawn(move || {
+        let mut i = 0;
+        while !stopped.load(Ordering::Relaxed) {
+            f(i);
+            i += 1;
+            if !interval.is_zero() {
+                thread::sleep(interval);
+            }
+        }
+    });
+    Contender {
+        stop,
+        handle: Some(handle),
+    }
+}
+
+impl Drop for Contender {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            handle.join().unwrap();
+        }
+    }
+}
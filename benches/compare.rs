@@ -0,0 +1,421 @@
+mod common;
+
+use crate::common::{contend_every, Contender};
+use arc_swap::ArcSwap;
+#[cfg(feature = "compare-bus")]
+use bus::Bus;
+use criterion::{criterion_group, criterion_main, Criterion};
+use rio_thread_bench::message::{Message, Payload};
+use rio_thread_bench::{channel, SeqLatest};
+use std::env;
+use std::hint::black_box;
+use std::mem;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+#[cfg(feature = "compare-triple-buffer")]
+use triple_buffer::triple_buffer;
+
+/// Spawns a `Contender` that calls `f` with an increasing counter, sleeping
+/// briefly between calls.
+fn contend(f: impl FnMut(u64) + Send + 'static) -> Contender {
+    contend_every(Duration::from_nanos(5), f)
+}
+
+/// A `left_right` cell holding a single value, whose only operation is
+/// replacing it.
+#[cfg(feature = "compare-left-right")]
+#[derive(Clone, Default)]
+struct LeftRight<M>(M);
+
+#[cfg(feature = "compare-left-right")]
+impl<M: Clone> left_right::Absorb<M> for LeftRight<M> {
+    fn absorb_first(&mut self, msg: &mut M, _: &Self) {
+        self.0 = msg.clone();
+    }
+
+    fn absorb_second(&mut self, msg: M, _: &Self) {
+        self.0 = msg;
+    }
+
+    fn drop_first(self: Box<Self>) {}
+
+    fn sync_with(&mut self, first: &Self) {
+        self.0 = first.0.clone();
+    }
+}
+
+/// Receives until the channel is empty, keeping only the newest message.
+#[cfg(any(feature = "compare-crossbeam", feature = "compare-flume"))]
+fn drain_latest<T>(mut try_recv: impl FnMut() -> Option<T>) -> Option<T> {
+    let mut latest = None;
+    while let Some(msg) = try_recv() {
+        latest = Some(msg);
+    }
+    latest
+}
+
+/// How many readers contend in each benchmark, including the measured one
+/// when measuring reads. Set with the `READERS` environment variable.
+fn readers() -> usize {
+    env::var("READERS")
+        .ok()
+        .and_then(|readers| readers.parse().ok())
+        .unwrap_or(3)
+}
+
+fn reads<M: Payload>(c: &mut Criterion, size: &str, readers: usize) {
+    let mut group = c.benchmark_group(format!("reads/{}/{}-readers", size, readers));
+    let others = readers.saturating_sub(1);
+
+    group.bench_function("latest", |b| {
+        // One slot for the published value, one for each reader that may
+        // still be holding its last read, and one to write into.
+        let (mut writer, reader) = channel(readers + 2);
+        let _readers: Vec<_> = (0..others)
+            .map(|_| {
+                let reader = reader.clone();
+                contend(move |_| {
+                    black_box(reader.get());
+                })
+            })
+            .collect();
+        let _writer = contend(move |i| writer.set(M::new(i as f64, -(i as f64))));
+        b.iter(|| black_box(reader.get().expect("q closed")));
+    });
+
+    group.bench_function("seqlock", |b| {
+        let mut writer = SeqLatest::new(M::new(0., 0.));
+        let reader = writer.reader();
+        let _readers: Vec<_> = (0..others)
+            .map(|_| {
+                let reader = reader.clone();
+                contend(move |_| {
+                    black_box(reader.get());
+                })
+            })
+            .collect();
+        let _writer = contend(move |i| writer.set(M::new(i as f64, -(i as f64))));
+        b.iter(|| black_box(reader.get().expect("q closed")));
+    });
+
+    group.bench_function("mutex", |b| {
+        let mutex = Arc::new(Mutex::new(M::new(0., 0.)));
+        let _readers: Vec<_> = (0..others)
+            .map(|_| {
+                let mutex = mutex.clone();
+                contend(move |_| {
+                    black_box(mutex.lock().unwrap().y());
+                })
+            })
+            .collect();
+        let writer = mutex.clone();
+        let _writer = contend(move |i| *writer.lock().unwrap() = M::new(i as f64, -(i as f64)));
+        b.iter(|| black_box(mutex.lock().unwrap().y()));
+    });
+
+    group.bench_function("rwlock", |b| {
+        let lock = Arc::new(RwLock::new(M::new(0., 0.)));
+        let _readers: Vec<_> = (0..others)
+            .map(|_| {
+                let lock = lock.clone();
+                contend(move |_| {
+                    black_box(lock.read().unwrap().y());
+                })
+            })
+            .collect();
+        let writer = lock.clone();
+        let _writer = contend(move |i| *writer.write().unwrap() = M::new(i as f64, -(i as f64)));
+        b.iter(|| black_box(lock.read().unwrap().y()));
+    });
+
+    // `parking_lot` queues readers behind a waiting writer, so neither side
+    // can starve the other the way `std`'s can on some platforms.
+    #[cfg(feature = "compare-parking-lot")]
+    group.bench_function("parking_lot_rwlock", |b| {
+        let lock = Arc::new(parking_lot::RwLock::new(M::new(0., 0.)));
+        let _readers: Vec<_> = (0..others)
+            .map(|_| {
+                let lock = lock.clone();
+                contend(move |_| {
+                    black_box(lock.read().y());
+                })
+            })
+            .collect();
+        let writer = lock.clone();
+        let _writer = contend(move |i| *writer.write() = M::new(i as f64, -(i as f64)));
+        b.iter(|| black_box(lock.read().y()));
+    });
+
+    #[cfg(feature = "compare-bus")]
+    group.bench_function("bus", |b| {
+        let mut bus = Bus::new(200);
+        let mut reader = bus.add_rx();
+        let _readers: Vec<_> = (0..others)
+            .map(|_| {
+                let mut reader = bus.add_rx();
+                contend(move |_| {
+                    black_box(reader.try_recv().ok());
+                })
+            })
+            .collect();
+        let _writer = contend(move |i| {
+            bus.try_broadcast(M::new(i as f64, -(i as f64))).ok();
+        });
+        b.iter(|| black_box(reader.try_recv().ok()));
+    });
+
+    // The channels are drained to the newest message on every read. Readers
+    // compete for messages rather than each seeing every one.
+    #[cfg(feature = "compare-flume")]
+    group.bench_function("flume", |b| {
+        let (tx, rx) = flume::bounded(200);
+        let _readers: Vec<_> = (0..others)
+            .map(|_| {
+                let rx = rx.clone();
+                contend(move |_| {
+                    black_box(drain_latest(|| rx.try_recv().ok()));
+                })
+            })
+            .collect();
+        let _writer = contend(move |i| {
+            tx.try_send(M::new(i as f64, -(i as f64))).ok();
+        });
+        b.iter(|| black_box(drain_latest(|| rx.try_recv().ok())));
+    });
+
+    #[cfg(feature = "compare-crossbeam")]
+    group.bench_function("crossbeam_channel", |b| {
+        let (tx, rx) = crossbeam_channel::bounded(200);
+        let _readers: Vec<_> = (0..others)
+            .map(|_| {
+                let rx = rx.clone();
+                contend(move |_| {
+                    black_box(drain_latest(|| rx.try_recv().ok()));
+                })
+            })
+            .collect();
+        let _writer = contend(move |i| {
+            tx.try_send(M::new(i as f64, -(i as f64))).ok();
+        });
+        b.iter(|| black_box(drain_latest(|| rx.try_recv().ok())));
+    });
+
+    group.bench_function("arcswap", |b| {
+        let swap = Arc::new(ArcSwap::from_pointee(M::new(0., 0.)));
+        let _readers: Vec<_> = (0..others)
+            .map(|_| {
+                let swap = swap.clone();
+                contend(move |_| {
+                    black_box(swap.load_full());
+                })
+            })
+            .collect();
+        let writer = swap.clone();
+        let _writer = contend(move |i| writer.store(Arc::new(M::new(i as f64, -(i as f64)))));
+        b.iter(|| black_box(swap.load_full()));
+    });
+
+    // Reads never wait on the writer, which instead waits for readers to
+    // leave the copy it is about to write to.
+    #[cfg(feature = "compare-left-right")]
+    group.bench_function("left_right", |b| {
+        let (mut writer, reader) = left_right::new::<LeftRight<M>, M>();
+        let _readers: Vec<_> = (0..others)
+            .map(|_| {
+                let reader = reader.clone();
+                contend(move |_| {
+                    black_box(reader.enter().map(|guard| guard.0.y()));
+                })
+            })
+            .collect();
+        let _writer = contend(move |i| {
+            writer.append(M::new(i as f64, -(i as f64)));
+            writer.publish();
+        });
+        // Copied out to match what `LatestReader::get` hands back.
+        b.iter(|| black_box(reader.enter().expect("q closed").0));
+    });
+
+    // A triple buffer only has a single consumer, so it ignores `readers`.
+    #[cfg(feature = "compare-triple-buffer")]
+    group.bench_function("triple_buffer", |b| {
+        let (mut input, mut output) = triple_buffer(&M::default());
+        let _writer = contend(move |i| input.write(M::new(i as f64, -(i as f64))));
+        // Copied out to match what `LatestReader::get` hands back.
+        b.iter(|| black_box(*output.read()));
+    });
+
+    group.finish();
+}
+
+fn writes<M: Payload>(c: &mut Criterion, size: &str, readers: usize) {
+    let mut group = c.benchmark_group(format!("writes/{}/{}-readers", size, readers));
+
+    group.bench_function("latest", |b| {
+        let (mut latest, reader) = channel(readers + 2);
+        let _readers: Vec<_> = (0..readers)
+            .map(|_| {
+                let reader = reader.clone();
+                contend(move |_| {
+                    black_box(reader.get());
+                })
+            })
+            .collect();
+        b.iter(|| latest.set(M::new(1., -1.)));
+    });
+
+    group.bench_function("seqlock", |b| {
+        let mut latest = SeqLatest::new(M::new(0., 0.));
+        let _readers: Vec<_> = (0..readers)
+            .map(|_| {
+                let reader = latest.reader();
+                contend(move |_| {
+                    black_box(reader.get());
+                })
+            })
+            .collect();
+        b.iter(|| latest.set(M::new(1., -1.)));
+    });
+
+    group.bench_function("mutex", |b| {
+        let mutex = Arc::new(Mutex::new(M::new(0., 0.)));
+        let _readers: Vec<_> = (0..readers)
+            .map(|_| {
+                let mutex = mutex.clone();
+                contend(move |_| {
+                    black_box(mutex.lock().unwrap().y());
+                })
+            })
+            .collect();
+        b.iter(|| *mutex.lock().unwrap() = M::new(1., -1.));
+    });
+
+    group.bench_function("rwlock", |b| {
+        let lock = Arc::new(RwLock::new(M::new(0., 0.)));
+        let _readers: Vec<_> = (0..readers)
+            .map(|_| {
+                let lock = lock.clone();
+                contend(move |_| {
+                    black_box(lock.read().unwrap().y());
+                })
+            })
+            .collect();
+        b.iter(|| *lock.write().unwrap() = M::new(1., -1.));
+    });
+
+    #[cfg(feature = "compare-parking-lot")]
+    group.bench_function("parking_lot_rwlock", |b| {
+        let lock = Arc::new(parking_lot::RwLock::new(M::new(0., 0.)));
+        let _readers: Vec<_> = (0..readers)
+            .map(|_| {
+                let lock = lock.clone();
+                contend(move |_| {
+                    black_box(lock.read().y());
+                })
+            })
+            .collect();
+        b.iter(|| *lock.write() = M::new(1., -1.));
+    });
+
+    #[cfg(feature = "compare-bus")]
+    group.bench_function("bus", |b| {
+        let mut bus = Bus::new(200);
+        let _readers: Vec<_> = (0..readers)
+            .map(|_| {
+                let mut reader = bus.add_rx();
+                contend(move |_| {
+                    black_box(reader.try_recv().ok());
+                })
+            })
+            .collect();
+        b.iter(|| bus.try_broadcast(M::new(1., -1.)).ok());
+    });
+
+    #[cfg(feature = "compare-flume")]
+    group.bench_function("flume", |b| {
+        let (tx, rx) = flume::bounded(200);
+        let _readers: Vec<_> = (0..readers)
+            .map(|_| {
+                let rx = rx.clone();
+                contend(move |_| {
+                    black_box(drain_latest(|| rx.try_recv().ok()));
+                })
+            })
+            .collect();
+        b.iter(|| tx.try_send(M::new(1., -1.)).ok());
+    });
+
+    #[cfg(feature = "compare-crossbeam")]
+    group.bench_function("crossbeam_channel", |b| {
+        let (tx, rx) = crossbeam_channel::bounded(200);
+        let _readers: Vec<_> = (0..readers)
+            .map(|_| {
+                let rx = rx.clone();
+                contend(move |_| {
+                    black_box(drain_latest(|| rx.try_recv().ok()));
+                })
+            })
+            .collect();
+        b.iter(|| tx.try_send(M::new(1., -1.)).ok());
+    });
+
+    group.bench_function("arcswap", |b| {
+        let swap = Arc::new(ArcSwap::from_pointee(M::new(0., 0.)));
+        let _readers: Vec<_> = (0..readers)
+            .map(|_| {
+                let swap = swap.clone();
+                contend(move |_| {
+                    black_box(swap.load_full());
+                })
+            })
+            .collect();
+        b.iter(|| swap.store(Arc::new(M::new(1., -1.))));
+    });
+
+    #[cfg(feature = "compare-left-right")]
+    group.bench_function("left_right", |b| {
+        let (mut writer, reader) = left_right::new::<LeftRight<M>, M>();
+        let _readers: Vec<_> = (0..readers)
+            .map(|_| {
+                let reader = reader.clone();
+                contend(move |_| {
+                    black_box(reader.enter().map(|guard| guard.0.y()));
+                })
+            })
+            .collect();
+        b.iter(|| {
+            writer.append(M::new(1., -1.));
+            writer.publish();
+        });
+    });
+
+    #[cfg(feature = "compare-triple-buffer")]
+    group.bench_function("triple_buffer", |b| {
+        let (mut input, mut output) = triple_buffer(&M::default());
+        // A triple buffer only has a single consumer to contend with.
+        let _reader = contend(move |_| {
+            black_box(output.read().y());
+        });
+        b.iter(|| input.write(M::new(1., -1.)));
+    });
+
+    group.finish();
+}
+
+/// Runs every benchmark for `M`, in groups named after its size in bytes.
+fn sized<M: Payload>(c: &mut Criterion, readers: usize) {
+    let size = format!("{}B", mem::size_of::<M>());
+    reads::<M>(c, &size, readers);
+    writes::<M>(c, &size, readers);
+}
+
+fn benches(c: &mut Criterion) {
+    let readers = readers();
+    sized::<Message<0>>(c, readers);
+    sized::<Message<48>>(c, readers);
+    sized::<Message<240>>(c, readers);
+    sized::<Message<1008>>(c, readers);
+}
+
+criterion_group!(compare, benches);
+criterion_main!(compare);
@@ -0,0 +1,127 @@
+//! Aggregate throughput of one writer and a few readers sharing a `Latest`,
+//! a `Mutex` or an `RwLock`, at a few read:write ratios, to find the ratio
+//! at which `Latest` stops coming out ahead.
+//!
+//! The writer aims for one write every `WRITE_INTERVAL` and the readers
+//! between them for `ratio` reads per write, each thread spinning until its
+//! next turn is due and catching up flat out once it falls behind. Where
+//! every thread keeps to its rate the rows come out equal, so the numbers
+//! only tell the contenders apart once the high ratios ask for more than
+//! they can deliver. Edit the consts to sweep other rates and ratios.
+
+use rio_thread_bench::channel;
+use rio_thread_bench::message::{Message, Payload};
+use std::hint::black_box;
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How many threads read, on top of the one writing.
+const READERS: u32 = 3;
+
+/// How often the writer aims to publish.
+const WRITE_INTERVAL: Duration = Duration::from_micros(10);
+
+/// Reads per write across all the readers.
+const RATIOS: [u32; 4] = [1, 10, 100, 1000];
+
+/// How long each contender runs at each ratio.
+const RUN: Duration = Duration::from_secs(1);
+
+/// Calls `op` every `interval` until `end`, and returns how many times it
+/// did.
+fn paced(interval: Duration, end: Instant, mut op: impl FnMut(u64)) -> u64 {
+    let mut next = Instant::now();
+    let mut done = 0;
+    loop {
+        let now = Instant::now();
+        if now >= end {
+            return done;
+        }
+        if now >= next {
+            op(done);
+            done += 1;
+            next += interval;
+        } else {
+            std::hint::spin_loop();
+        }
+    }
+}
+
+/// Runs `write` and `READERS` threads of `read` for `RUN`, with the readers
+/// paced for `ratio` reads per write, and returns the operations per second
+/// of all of them together.
+fn run(ratio: u32, write: impl FnMut(u64) + Send, read: impl Fn() + Sync) -> f64 {
+    let read_interval = WRITE_INTERVAL * READERS / ratio;
+    let end = Instant::now() + RUN;
+    let ops: u64 = thread::scope(|s| {
+        let writer = s.spawn(move || paced(WRITE_INTERVAL, end, write));
+        let readers: Vec<_> = (0..READERS)
+            .map(|_| s.spawn(|| paced(read_interval, end, |_| read())))
+            .collect();
+        writer.join().unwrap() + readers.into_iter().map(|r| r.join().unwrap()).sum::<u64>()
+    });
+    ops as f64 / RUN.as_secs_f64()
+}
+
+fn msg(i: u64) -> Message {
+    Message::new(i as f64, -(i as f64))
+}
+
+fn contenders(ratio: u32) -> Vec<(&'static str, f64)> {
+    let mut results = Vec::new();
+
+    let (mut writer, reader) = channel::<Message>(READERS as usize + 2);
+    results.push((
+        "latest",
+        run(
+            ratio,
+            |i| writer.set(msg(i)),
+            || {
+                black_box(reader.get().expect("q closed"));
+            },
+        ),
+    ));
+
+    let mutex = Arc::new(Mutex::new(msg(0)));
+    results.push((
+        "mutex",
+        run(
+            ratio,
+            |i| *mutex.lock().unwrap() = msg(i),
+            || {
+                black_box(*mutex.lock().unwrap());
+            },
+        ),
+    ));
+
+    let lock = Arc::new(RwLock::new(msg(0)));
+    results.push((
+        "rwlock",
+        run(
+            ratio,
+            |i| *lock.write().unwrap() = msg(i),
+            || {
+                black_box(*lock.read().unwrap());
+            },
+        ),
+    ));
+
+    results
+}
+
+fn main() {
+    let columns: Vec<_> = RATIOS.iter().map(|&ratio| contenders(ratio)).collect();
+    print!("{:>8}", "Mops/s");
+    for ratio in RATIOS.iter() {
+        print!(" {:>9}", format!("{}:1", ratio));
+    }
+    println!();
+    for (row, &(name, _)) in columns[0].iter().enumerate() {
+        print!("{:>8}", name);
+        for column in columns.iter() {
+            print!(" {:>9.3}", column[row].1 / 1e6);
+        }
+        println!();
+    }
+}
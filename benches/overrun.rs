@@ -0,0 +1,162 @@
+//! Writer throughput when the writer never sleeps and the readers sleep
+//! between every read, for `Latest` next to a `Bus` and bounded channels.
+//!
+//! `Latest` overwrites whatever the readers haven't got to, so its writer
+//! never waits on them. A queue has to either hold the writer up until the
+//! readers make room or turn values away once it is full, and this prints
+//! how fast each went and, for those that turn values away, how many did.
+//! `bus` blocks in `broadcast`, `bus_try` drops through `try_broadcast`,
+//! and the channels drop through `try_send`. An `mpsc` receiver can't be
+//! shared, so that row has one reader rather than `READERS`.
+
+use rio_thread_bench::channel;
+use rio_thread_bench::message::{Message, Payload};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const READERS: usize = 3;
+
+/// How long each reader sleeps after every read.
+const READER_SLEEP: Duration = Duration::from_millis(1);
+
+/// How long the writer runs for each contender.
+const RUN: Duration = Duration::from_secs(1);
+
+/// How many values the queues hold before they block or drop.
+const BOUND: usize = 200;
+
+/// Calls `write` flat out for `RUN` while each of `readers` runs once a
+/// `READER_SLEEP`, and returns how many writes went through and how many
+/// `write` reported dropped a value.
+fn run<R: FnMut() + Send>(mut write: impl FnMut(u64) -> bool, readers: Vec<R>) -> (u64, u64) {
+    let done = AtomicBool::new(false);
+    thread::scope(|s| {
+        for mut read in readers {
+            let done = &done;
+            s.spawn(move || {
+                while !done.load(Ordering::Relaxed) {
+                    read();
+                    thread::sleep(READER_SLEEP);
+                }
+            });
+        }
+        let end = Instant::now() + RUN;
+        let (mut written, mut dropped) = (0, 0);
+        let mut i = 0;
+        while Instant::now() < end {
+            if write(i) {
+                written += 1;
+            } else {
+                dropped += 1;
+            }
+            i += 1;
+        }
+        done.store(true, Ordering::Relaxed);
+        (written, dropped)
+    })
+}
+
+fn msg(i: u64) -> Message {
+    Message::new(i as f64, -(i as f64))
+}
+
+/// Returns each contender's writes and drops, or `None` for drops where it
+/// never drops anything.
+fn contenders() -> Vec<(&'static str, u64, Option<u64>)> {
+    let mut results = Vec::new();
+
+    let (mut writer, reader) = channel::<Message>(READERS + 2);
+    let readers = (0..READERS)
+        .map(|_| {
+            let reader = reader.clone();
+            move || {
+                black_box(reader.get());
+            }
+        })
+        .collect();
+    let (written, _) = run(
+        |i| {
+            writer.set(msg(i));
+            true
+        },
+        readers,
+    );
+    results.push(("latest", written, None));
+
+    #[cfg(feature = "compare-bus")]
+    {
+        let mut bus = bus::Bus::<Message>::new(BOUND);
+        let readers = (0..READERS)
+            .map(|_| {
+                let mut rx = bus.add_rx();
+                move || {
+                    black_box(rx.try_recv().ok());
+                }
+            })
+            .collect();
+        let (written, _) = run(
+            |i| {
+                bus.broadcast(msg(i));
+                true
+            },
+            readers,
+        );
+        results.push(("bus", written, None));
+
+        let mut bus = bus::Bus::<Message>::new(BOUND);
+        let readers = (0..READERS)
+            .map(|_| {
+                let mut rx = bus.add_rx();
+                move || {
+                    black_box(rx.try_recv().ok());
+                }
+            })
+            .collect();
+        let (written, dropped) = run(|i| bus.try_broadcast(msg(i)).is_ok(), readers);
+        results.push(("bus_try", written, Some(dropped)));
+    }
+
+    let (tx, rx) = mpsc::sync_channel::<Message>(BOUND);
+    let readers = vec![move || {
+        black_box(rx.try_recv().ok());
+    }];
+    let (written, dropped) = run(|i| tx.try_send(msg(i)).is_ok(), readers);
+    results.push(("mpsc", written, Some(dropped)));
+
+    #[cfg(feature = "compare-crossbeam")]
+    {
+        let (tx, rx) = crossbeam_channel::bounded::<Message>(BOUND);
+        let readers = (0..READERS)
+            .map(|_| {
+                let rx = rx.clone();
+                move || {
+                    black_box(rx.try_recv().ok());
+                }
+            })
+            .collect();
+        let (written, dropped) = run(|i| tx.try_send(msg(i)).is_ok(), readers);
+        results.push(("crossbeam_channel", written, Some(dropped)));
+    }
+
+    results
+}
+
+fn main() {
+    let secs = RUN.as_secs_f64();
+    println!("{:>18} {:>12} {:>12}", "", "writes/s", "dropped/s");
+    for (name, written, dropped) in contenders() {
+        let dropped = match dropped {
+            Some(dropped) => format!("{:.0}", dropped as f64 / secs),
+            None => "-".into(),
+        };
+        println!(
+            "{:>18} {:>12.0} {:>12}",
+            name,
+            written as f64 / secs,
+            dropped
+        );
+    }
+}
@@ -0,0 +1,120 @@
+//! The `latest` and `mutex` read benchmarks from `compare`, run once with
+//! every thread left to the scheduler and once with each pinned to a core of
+//! its own, to tell how much of the gap between them is scheduling.
+//!
+//! The measured reader is pinned to the first core, the writer to the second
+//! and the other readers to the ones after, wrapping round if there are more
+//! threads than cores. Pinning can't be undone for the measured thread, so the
+//! unpinned groups run first.
+
+mod common;
+
+use crate::common::{contend_every, Contender};
+use core_affinity::CoreId;
+use criterion::{criterion_group, criterion_main, Criterion};
+use rio_thread_bench::channel;
+use rio_thread_bench::message::{Message, Payload};
+use std::env;
+use std::hint::black_box;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Like `compare`'s `contend`, but first pins the contending thread to `core`
+/// if there is one.
+fn contend_on(core: Option<CoreId>, mut f: impl FnMut(u64) + Send + 'static) -> Contender {
+    let mut unpinned = core;
+    contend_every(Duration::from_nanos(5), move |i| {
+        if let Some(core) = unpinned.take() {
+            core_affinity::set_for_current(core);
+        }
+        f(i)
+    })
+}
+
+/// Like `compare`'s `readers`.
+fn readers() -> usize {
+    env::var("READERS")
+        .ok()
+        .and_then(|readers| readers.parse().ok())
+        .unwrap_or(3)
+}
+
+/// Hands out the core for each thread in turn, or `None` for every thread
+/// when not pinning.
+struct Cores(Option<Vec<CoreId>>, usize);
+
+impl Cores {
+    fn new(pin: bool) -> Self {
+        let cores = if pin {
+            core_affinity::get_core_ids().filter(|cores| !cores.is_empty())
+        } else {
+            None
+        };
+        Cores(cores, 0)
+    }
+
+    fn next(&mut self) -> Option<CoreId> {
+        let cores = self.0.as_ref()?;
+        let core = cores[self.1 % cores.len()];
+        self.1 += 1;
+        Some(core)
+    }
+}
+
+fn reads(c: &mut Criterion, pin: bool, readers: usize) {
+    let name = if pin { "pinned" } else { "unpinned" };
+    let mut group = c.benchmark_group(format!("{}/reads/{}-readers", name, readers));
+    let others = readers.saturating_sub(1);
+
+    group.bench_function("latest", |b| {
+        let mut cores = Cores::new(pin);
+        if let Some(core) = cores.next() {
+            core_affinity::set_for_current(core);
+        }
+        let (mut writer, reader) = channel::<Message>(readers + 2);
+        let _writer = contend_on(cores.next(), move |i| {
+            writer.set(Message::new(i as f64, -(i as f64)))
+        });
+        let _readers: Vec<_> = (0..others)
+            .map(|_| {
+                let reader = reader.clone();
+                contend_on(cores.next(), move |_| {
+                    black_box(reader.get());
+                })
+            })
+            .collect();
+        b.iter(|| black_box(reader.get().expect("q closed")));
+    });
+
+    group.bench_function("mutex", |b| {
+        let mut cores = Cores::new(pin);
+        if let Some(core) = cores.next() {
+            core_affinity::set_for_current(core);
+        }
+        let mutex = Arc::new(Mutex::new(<Message>::new(0., 0.)));
+        let writer = mutex.clone();
+        let _writer = contend_on(cores.next(), move |i| {
+            *writer.lock().unwrap() = Message::new(i as f64, -(i as f64))
+        });
+        let _readers: Vec<_> = (0..others)
+            .map(|_| {
+                let mutex = mutex.clone();
+                contend_on(cores.next(), move |_| {
+                    black_box(mutex.lock().unwrap().y());
+                })
+            })
+            .collect();
+        b.iter(|| black_box(mutex.lock().unwrap().y()));
+    });
+
+    group.finish();
+}
+
+fn benches(c: &mut Criterion) {
+    let readers = readers();
+    reads(c, false, readers);
+    reads(c, true, readers);
+}
+
+criterion_group!(pinning, benches);
+criterion_main!(pinning);
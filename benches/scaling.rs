@@ -0,0 +1,72 @@
+//! How reads from a `Latest` scale as more readers hammer it at once, with
+//! no writer and no sleeping between reads.
+//!
+//! `get_arc` bumps the reference count of the same `Arc` on every call, so
+//! every reader fights over that cache line. `get` and `guard` read through
+//! an `arc_swap` guard instead, which leaves the count alone. Set
+//! `MAX_READERS` to scale past the default of 8.
+
+mod common;
+
+use crate::common::{contend_every, Contender};
+use criterion::{criterion_group, criterion_main, Criterion};
+use rio_thread_bench::message::{Message, Payload};
+use rio_thread_bench::{channel, LatestReader};
+use std::env;
+use std::hint::black_box;
+use std::time::Duration;
+
+/// One way of reading from a `LatestReader`.
+type Read = fn(&LatestReader<Message>);
+
+fn max_readers() -> usize {
+    env::var("MAX_READERS")
+        .ok()
+        .and_then(|readers| readers.parse().ok())
+        .unwrap_or(8)
+}
+
+/// Spawns `n` readers spinning on `read` against clones of `reader`.
+fn spin_readers(reader: &LatestReader<Message>, n: usize, read: Read) -> Vec<Contender> {
+    (0..n)
+        .map(|_| {
+            let reader = reader.clone();
+            contend_every(Duration::ZERO, move |_| read(&reader))
+        })
+        .collect()
+}
+
+fn reads(c: &mut Criterion, readers: usize) {
+    let mut group = c.benchmark_group(format!("scaling/{}-readers", readers));
+    let others = readers - 1;
+    let paths: [(&str, Read); 3] = [
+        ("get", |reader| {
+            black_box(reader.get());
+        }),
+        ("get_arc", |reader| {
+            black_box(reader.get_arc());
+        }),
+        ("guard", |reader| {
+            black_box(reader.guard().map(|guard| guard.y()));
+        }),
+    ];
+    for &(name, read) in paths.iter() {
+        group.bench_function(name, |b| {
+            let (_writer, reader) = channel::<Message>(2);
+            let _readers = spin_readers(&reader, others, read);
+            b.iter(|| read(&reader));
+        });
+    }
+    group.finish();
+}
+
+fn benches(c: &mut Criterion) {
+    let mut readers = 1;
+    while readers <= max_readers() {
+        reads(c, readers);
+        readers *= 2;
+    }
+}
+
+criterion_group!(scaling, benches);
+criterion_main!(scaling);
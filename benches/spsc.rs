@@ -0,0 +1,79 @@
+//! `SpscLatest` against `Latest` with the one reader it is built for, at a
+//! couple of payload sizes.
+//!
+//! `latest` clones the value out on every read, while `spsc` only borrows it,
+//! so the `spsc` reads touch the payload through `y` to keep them honest.
+
+mod common;
+
+use crate::common::contend_every;
+use criterion::{criterion_group, criterion_main, Criterion};
+use rio_thread_bench::message::{Message, Payload};
+use rio_thread_bench::{channel, SpscLatest};
+use std::hint::black_box;
+use std::mem;
+use std::time::Duration;
+
+fn reads<M: Payload>(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("spsc/reads/{}B", mem::size_of::<M>()));
+
+    group.bench_function("latest", |b| {
+        let (mut writer, reader) = channel(3);
+        let _writer = contend_every(Duration::from_nanos(5), move |i| {
+            writer.set(M::new(i as f64, -(i as f64)))
+        });
+        b.iter(|| black_box(reader.get().expect("q closed")));
+    });
+
+    group.bench_function("spsc", |b| {
+        let mut writer = SpscLatest::new(M::new(0., 0.));
+        let mut reader = writer.reader().unwrap();
+        let _writer = contend_every(Duration::from_nanos(5), move |i| {
+            writer.set(M::new(i as f64, -(i as f64)))
+        });
+        b.iter(|| black_box(reader.get().expect("q closed").y()));
+    });
+
+    group.finish();
+}
+
+fn writes<M: Payload>(c: &mut Criterion) {
+    let mut group = c.benchmark_group(format!("spsc/writes/{}B", mem::size_of::<M>()));
+
+    group.bench_function("latest", |b| {
+        let (mut writer, reader) = channel::<M>(3);
+        let _reader = contend_every(Duration::from_nanos(5), move |_| {
+            black_box(reader.get());
+        });
+        let mut i = 0;
+        b.iter(|| {
+            writer.set(M::new(i as f64, -(i as f64)));
+            i += 1;
+        });
+    });
+
+    group.bench_function("spsc", |b| {
+        let mut writer = SpscLatest::new(M::new(0., 0.));
+        let mut reader = writer.reader().unwrap();
+        let _reader = contend_every(Duration::from_nanos(5), move |_| {
+            black_box(reader.get().map(|msg| msg.y()));
+        });
+        let mut i = 0;
+        b.iter(|| {
+            writer.set(M::new(i as f64, -(i as f64)));
+            i += 1;
+        });
+    });
+
+    group.finish();
+}
+
+fn benches(c: &mut Criterion) {
+    reads::<Message<0>>(c);
+    reads::<Message<1008>>(c);
+    writes::<Message<0>>(c);
+    writes::<Message<1008>>(c);
+}
+
+criterion_group!(spsc, benches);
+criterion_main!(spsc);
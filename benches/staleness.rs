@@ -0,0 +1,129 @@
+//! How far behind the newest value a reader is by the time it has its copy,
+//! for a few payload sizes and writer cadences, with `Latest` next to a
+//! queue.
+//!
+//! The reader runs at a fixed period, like a control loop, so a writer faster
+//! than that leaves a queue with a backlog. Criterion only measures time, so
+//! this prints its own table of the gap in versions between the value a read
+//! returned and the latest one published just after it.
+//!
+//! The queue is a `Bus`, so its columns need the `compare-bus` feature.
+
+mod common;
+
+use crate::common::contend_every;
+#[cfg(feature = "compare-bus")]
+use bus::Bus;
+use rio_thread_bench::channel;
+use rio_thread_bench::message::{Message, Payload};
+use std::mem;
+#[cfg(feature = "compare-bus")]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "compare-bus")]
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+const READS: usize = 5_000;
+
+/// How long the reader sleeps between reads.
+const READ_PERIOD: Duration = Duration::from_micros(200);
+
+/// Sleeps between writes. The first is what the `compare` benches use.
+const INTERVALS: [Duration; 4] = [
+    Duration::from_nanos(5),
+    Duration::from_micros(1),
+    Duration::from_micros(10),
+    Duration::from_micros(100),
+];
+
+/// Reads `READS` times while a writer publishes to a `Latest` every
+/// `interval`, returning the version gap seen on each read.
+fn latest_gaps<M: Payload>(interval: Duration) -> Vec<u64> {
+    let (mut writer, reader) = channel::<M>(3);
+    // The `n`th value published is version `n`, and carries it in `y`.
+    let _writer = contend_every(interval, move |i| writer.set(M::new(0., (i + 1) as f64)));
+    (0..READS)
+        .map(|_| {
+            thread::sleep(READ_PERIOD);
+            let msg = reader.get().expect("q closed");
+            let latest = reader.version().expect("q closed");
+            // The version is bumped just after the value is stored, so it can
+            // briefly trail the value read.
+            latest.saturating_sub(msg.y() as u64)
+        })
+        .collect()
+}
+
+/// Like `latest_gaps`, but through a `Bus`, where a reader that falls behind
+/// works through the backlog one message at a time. Reads that find the bus
+/// empty are not counted.
+#[cfg(feature = "compare-bus")]
+fn bus_gaps<M: Payload>(interval: Duration) -> Vec<u64> {
+    let mut bus = Bus::new(200);
+    let mut reader = bus.add_rx();
+    let published = Arc::new(AtomicU64::new(0));
+    let counter = published.clone();
+    // Only numbered when the broadcast goes through, since a full bus drops
+    // the message.
+    let mut sent = 0;
+    let _writer = contend_every(interval, move |_| {
+        if bus.try_broadcast(M::new(0., (sent + 1) as f64)).is_ok() {
+            sent += 1;
+            counter.store(sent, Ordering::Release);
+        }
+    });
+    (0..READS)
+        .filter_map(|_| {
+            thread::sleep(READ_PERIOD);
+            let msg = reader.try_recv().ok()?;
+            let latest = published.load(Ordering::Acquire);
+            Some(latest.saturating_sub(msg.y() as u64))
+        })
+        .collect()
+}
+
+/// Returns the mean and 99th percentile of `gaps`.
+fn summarize(mut gaps: Vec<u64>) -> (f64, u64) {
+    if gaps.is_empty() {
+        return (0., 0);
+    }
+    gaps.sort_unstable();
+    let mean = gaps.iter().sum::<u64>() as f64 / gaps.len() as f64;
+    (mean, gaps[gaps.len() * 99 / 100])
+}
+
+/// Prints a row for each writer cadence with payloads of type `M`.
+fn rows<M: Payload>() {
+    let size = format!("{}B", mem::size_of::<M>());
+    for &interval in INTERVALS.iter() {
+        let (latest_mean, latest_p99) = summarize(latest_gaps::<M>(interval));
+        print!(
+            "{:>6} {:>14} {:>12.3} {:>12}",
+            size,
+            format!("{:?}", interval),
+            latest_mean,
+            latest_p99
+        );
+        #[cfg(feature = "compare-bus")]
+        {
+            let (bus_mean, bus_p99) = summarize(bus_gaps::<M>(interval));
+            print!(" {:>12.3} {:>12}", bus_mean, bus_p99);
+        }
+        println!();
+    }
+}
+
+fn main() {
+    print!(
+        "{:>6} {:>14} {:>12} {:>12}",
+        "size", "writer sleep", "latest mean", "latest p99"
+    );
+    #[cfg(feature = "compare-bus")]
+    print!(" {:>12} {:>12}", "bus mean", "bus p99");
+    println!();
+    rows::<Message<0>>();
+    rows::<Message<48>>();
+    rows::<Message<240>>();
+    rows::<Message<1008>>();
+}
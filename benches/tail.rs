@@ -0,0 +1,92 @@
+//! The spread of single-read latencies under a contending writer, for
+//! `Latest` next to a `Mutex` and an `RwLock`.
+//!
+//! Criterion reports means, but a control loop cares about the slowest reads,
+//! which a lock can stretch out to however long the writer holds it. Every
+//! read is timed on its own and recorded in a histogram, and this prints
+//! percentiles in a table like `staleness`. The two `Instant::now` calls
+//! around each read add a constant few tens of nanoseconds to every row.
+
+mod common;
+
+use crate::common::contend_every;
+use hdrhistogram::Histogram;
+use rio_thread_bench::channel;
+use rio_thread_bench::message::{Message, Payload};
+use std::hint::black_box;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+const READS: usize = 1_000_000;
+
+const PERCENTILES: [f64; 4] = [50., 99., 99.9, 99.99];
+
+/// Times `READS` calls to `read`, in nanoseconds.
+fn record(mut read: impl FnMut()) -> Histogram<u64> {
+    let mut histogram = Histogram::new(3).unwrap();
+    for _ in 0..READS {
+        let start = Instant::now();
+        read();
+        histogram.saturating_record(start.elapsed().as_nanos() as u64);
+    }
+    histogram
+}
+
+fn contenders() -> Vec<(&'static str, Histogram<u64>)> {
+    let mut results = Vec::new();
+    let interval = Duration::from_nanos(5);
+
+    let (mut writer, reader) = channel::<Message>(3);
+    let _writer = contend_every(interval, move |i| {
+        writer.set(Message::new(i as f64, -(i as f64)))
+    });
+    results.push((
+        "latest",
+        record(|| {
+            black_box(reader.get().expect("q closed"));
+        }),
+    ));
+    drop(_writer);
+
+    let mutex = Arc::new(Mutex::new(<Message>::new(0., 0.)));
+    let writer = mutex.clone();
+    let _writer = contend_every(interval, move |i| {
+        *writer.lock().unwrap() = Message::new(i as f64, -(i as f64))
+    });
+    results.push((
+        "mutex",
+        record(|| {
+            black_box(*mutex.lock().unwrap());
+        }),
+    ));
+    drop(_writer);
+
+    let lock = Arc::new(RwLock::new(<Message>::new(0., 0.)));
+    let writer = lock.clone();
+    let _writer = contend_every(interval, move |i| {
+        *writer.write().unwrap() = Message::new(i as f64, -(i as f64))
+    });
+    results.push((
+        "rwlock",
+        record(|| {
+            black_box(*lock.read().unwrap());
+        }),
+    ));
+
+    results
+}
+
+fn main() {
+    print!("{:>8}", "ns");
+    for p in PERCENTILES.iter() {
+        print!(" {:>9}", format!("p{}", p));
+    }
+    println!(" {:>9}", "max");
+    for (name, histogram) in contenders() {
+        print!("{:>8}", name);
+        for &p in PERCENTILES.iter() {
+            print!(" {:>9}", histogram.value_at_quantile(p / 100.));
+        }
+        println!(" {:>9}", histogram.max());
+    }
+}
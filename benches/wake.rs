@@ -0,0 +1,69 @@
+//! How long a reader blocked in `wait_new` takes to wake up after a value is
+//! published, depending on how much it spins before sleeping.
+//!
+//! Each value carries the time it was published at, and the reader records
+//! how long after that it got it. Criterion only measures time spent in the
+//! measured closure, so this prints its own table like `staleness`.
+
+mod common;
+
+use crate::common::contend_every;
+use rio_thread_bench::channel;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const WAKES: usize = 2_000;
+
+/// Spin counts to compare: always sleeping, the default, and spinning long
+/// enough to catch most values without sleeping at all.
+const SPINS: [u32; 3] = [0, 100, 100_000];
+
+/// Sleeps between writes.
+const INTERVALS: [Duration; 3] = [
+    Duration::from_nanos(5),
+    Duration::from_micros(10),
+    Duration::from_micros(100),
+];
+
+/// Returns how long each of `WAKES` values took to reach a reader spinning
+/// `spins` times before sleeping, with a writer publishing every `interval`.
+fn latencies(spins: u32, interval: Duration) -> Vec<Duration> {
+    let (mut writer, reader) = channel::<Option<Instant>>(3);
+    let mut reader = reader.with_spins(spins);
+    let waiter = thread::spawn(move || {
+        (0..WAKES)
+            .map(|_| {
+                let sent = reader.wait_new().expect("q closed").unwrap();
+                sent.elapsed()
+            })
+            .collect()
+    });
+    let _writer = contend_every(interval, move |_| writer.set(Some(Instant::now())));
+    waiter.join().unwrap()
+}
+
+/// Returns the mean and 99th percentile of `latencies`.
+fn summarize(mut latencies: Vec<Duration>) -> (Duration, Duration) {
+    latencies.sort_unstable();
+    let mean = latencies.iter().sum::<Duration>() / latencies.len() as u32;
+    (mean, latencies[latencies.len() * 99 / 100])
+}
+
+fn main() {
+    println!(
+        "{:>14} {:>8} {:>12} {:>12}",
+        "writer sleep", "spins", "mean", "p99"
+    );
+    for &interval in INTERVALS.iter() {
+        for &spins in SPINS.iter() {
+            let (mean, p99) = summarize(latencies(spins, interval));
+            println!(
+                "{:>14} {:>8} {:>12} {:>12}",
+                format!("{:?}", interval),
+                spins,
+                format!("{:?}", mean),
+                format!("{:?}", p99)
+            );
+        }
+    }
+}
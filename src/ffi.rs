@@ -0,0 +1,39 @@
+//! A C entry point for reading a `Latest<Message>`, so that a thread outside
+//! of Rust can poll the latest value.
+//!
+//! The C side never makes or frees a reader itself. Rust hands it a pointer
+//! to a `LatestReader<Message>` it keeps alive, and C passes that back in
+//! along with a `Message` of its own to copy into. `Message` is `repr(C)`, so
+//! for the default `N` of 80 it matches
+//!
+//! ```c
+//! struct Message { double x; double y; uint8_t dummy[80]; };
+//! ```
+//!
+//! A `LatestReader` is `Sync`, so any number of C threads can read through
+//! the same pointer at once, even while the writer publishes.
+
+use crate::latest::LatestReader;
+use crate::message::Message;
+
+/// Copies the latest value of `reader` into `out` and returns `true`, or
+/// returns `false` and leaves `out` alone if the `Latest` has been dropped.
+///
+/// # Safety
+///
+/// `reader` must point to a live `LatestReader<Message>` and stay valid until
+/// this returns, and `out` must be valid for writing a `Message` that nothing
+/// else is reading or writing meanwhile.
+#[no_mangle]
+pub unsafe extern "C" fn latest_reader_get(
+    reader: *const LatestReader<Message>,
+    out: *mut Message,
+) -> bool {
+    match (*reader).get() {
+        Some(msg) => {
+            out.write(msg);
+            true
+        }
+        None => false,
+    }
+}
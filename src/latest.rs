@@ -0,0 +1,1994 @@
+use crate::sync::{self, Arc, ArcSwap, AtomicBool, AtomicU64, CachePadded, Guard, Ordering, Weak};
+#[cfg(feature = "std")]
+use crate::sync::{AtomicUsize, Condvar, Mutex};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem;
+use core::ops::Deref;
+use core::ptr::NonNull;
+#[cfg(feature = "std")]
+use std::error::Error;
+#[cfg(feature = "std")]
+use std::string::String;
+#[cfg(feature = "std")]
+use std::sync::OnceLock;
+#[cfg(feature = "async")]
+use std::task::Waker;
+#[cfg(feature = "std")]
+use std::time::{Duration, Instant};
+#[cfg(feature = "zeroize")]
+use zeroize::Zeroize;
+
+mod builder;
+#[cfg(feature = "std")]
+mod history;
+#[cfg(feature = "std")]
+mod rate_limited;
+#[cfg(feature = "std")]
+mod shared_writer;
+#[cfg(feature = "async")]
+mod stream;
+mod strong_reader;
+#[cfg(feature = "async")]
+mod watch;
+
+pub use self::builder::LatestBuilder;
+#[cfg(feature = "std")]
+pub use self::history::HistoryReader;
+#[cfg(feature = "std")]
+pub use self::rate_limited::RateLimited;
+#[cfg(feature = "std")]
+pub use self::shared_writer::SharedWriter;
+#[cfg(feature = "async")]
+pub use self::stream::LatestStream;
+pub use self::strong_reader::StrongReader;
+
+/// Versions are half of a `u64` sequence, so they count publishes modulo
+/// 2^63 and wrap round to 0 after this one.
+const MAX_VERSION: u64 = u64::MAX >> 1;
+
+/// How many times a read retries in a spin loop, while a publish is in
+/// flight, before it starts yielding instead.
+const READ_SPINS: u32 = 64;
+
+/// Returns how many values were published from version `older` up to
+/// `newer`, allowing for the count having wrapped round past 0 in between.
+#[inline]
+fn versions_since(newer: u64, older: u64) -> u64 {
+    newer.wrapping_sub(older) & MAX_VERSION
+}
+
+/// Returns whether version `a` was published after version `b`, allowing for
+/// wrapping.
+///
+/// Versions more than half the range apart, 2^62 publishes, read as the
+/// wrong way round. At a publish a nanosecond that is over a century.
+#[inline]
+fn version_newer(a: u64, b: u64) -> bool {
+    let since = versions_since(a, b);
+    since != 0 && since <= MAX_VERSION / 2
+}
+
+/// The state shared between a `Latest` and its readers.
+///
+/// `seq` is bumped once before each value is stored and once after, so it is
+/// odd while a publish is in flight and half of it is the version: the number
+/// of values published through `set`. It is monotonic until it wraps on `u64`
+/// overflow, and everything comparing versions goes through `version_newer`
+/// and `versions_since` so that the wrap goes unnoticed.
+///
+/// Readers blocked in `wait_new` sleep on `cond`, and async readers park their
+/// `Waker` in `wakers`. The writer only takes `lock` to wake them when
+/// `waiters` says someone is actually asleep. Without the `std` feature there
+/// is nothing to sleep on, so none of these exist.
+///
+/// `poison` holds the reason given to `Latest::poison`, set at most once.
+///
+/// `latest` gets a cache line to itself. Otherwise it would share one with
+/// the reference counts of the `Arc` this lives in, which every reader bumps
+/// on each read, and the writer's stores would keep stealing it back.
+#[derive(Debug)]
+struct Shared<M> {
+    latest: CachePadded<ArcSwap<M>>,
+    seq: AtomicU64,
+    closed: AtomicBool,
+    #[cfg(feature = "std")]
+    poison: OnceLock<String>,
+    /// When the latest value was published, in nanoseconds since `epoch`.
+    #[cfg(feature = "latency")]
+    published_at: AtomicU64,
+    #[cfg(feature = "latency")]
+    epoch: Instant,
+    #[cfg(feature = "std")]
+    waiters: AtomicUsize,
+    #[cfg(feature = "std")]
+    lock: Mutex<()>,
+    #[cfg(feature = "std")]
+    cond: Condvar,
+    #[cfg(feature = "async")]
+    wakers: Mutex<Vec<Waker>>,
+    /// The version each reader last marked as seen, for `Latest::reader_lags`.
+    /// Each reader owns its counter, so dropped ones are easy to spot. Only
+    /// made once `reader_lags` is first called, so that readers cost nothing
+    /// extra until then.
+    #[cfg(feature = "std")]
+    seen: OnceLock<Mutex<Vec<Weak<AtomicU64>>>>,
+}
+
+impl<M> Shared<M> {
+    fn new(latest: Arc<M>) -> Self {
+        Self {
+            latest: CachePadded(ArcSwap::new(latest)),
+            seq: AtomicU64::new(0),
+            closed: AtomicBool::new(false),
+            #[cfg(feature = "std")]
+            poison: OnceLock::new(),
+            #[cfg(feature = "latency")]
+            published_at: AtomicU64::new(0),
+            #[cfg(feature = "latency")]
+            epoch: Instant::now(),
+            #[cfg(feature = "std")]
+            waiters: AtomicUsize::new(0),
+            #[cfg(feature = "std")]
+            lock: Mutex::new(()),
+            #[cfg(feature = "std")]
+            cond: Condvar::new(),
+            #[cfg(feature = "async")]
+            wakers: Mutex::new(Vec::new()),
+            #[cfg(feature = "std")]
+            seen: OnceLock::new(),
+        }
+    }
+
+    /// Swaps `msg` in as the latest value and returns its version.
+    #[inline]
+    fn publish(&self, msg: Arc<M>) -> u64 {
+        self.seq.fetch_add(1, Ordering::SeqCst);
+        self.finish_publish(msg)
+    }
+
+    /// Like `publish`, but only if the version is still `expected`, handing
+    /// back the actual version otherwise.
+    ///
+    /// The first bump of `seq` is a compare-and-swap from the even value for
+    /// `expected`, so of several writers expecting the same version only one
+    /// gets to publish. A publish already in flight counts as done.
+    #[cfg(feature = "std")]
+    #[inline]
+    fn publish_if(&self, expected: u64, msg: Arc<M>) -> Result<u64, u64> {
+        let seq = expected.wrapping_mul(2);
+        if let Err(actual) =
+            self.seq
+                .compare_exchange(seq, seq.wrapping_add(1), Ordering::SeqCst, Ordering::SeqCst)
+        {
+            return Err(actual.wrapping_add(1) / 2);
+        }
+        Ok(self.finish_publish(msg))
+    }
+
+    /// Does the rest of a publish once `seq` has been bumped the first time.
+    #[inline]
+    fn finish_publish(&self, msg: Arc<M>) -> u64 {
+        // Stamped while `seq` is odd, so readers pair it with this value.
+        #[cfg(feature = "latency")]
+        self.published_at
+            .store(self.epoch.elapsed().as_nanos() as u64, Ordering::Release);
+        self.latest.store(msg);
+        // The second bump is `SeqCst`, like the `waiters` load, so a reader
+        // going to sleep either sees the new version or gets woken.
+        let version = self.seq.fetch_add(1, Ordering::SeqCst).wrapping_add(1) / 2;
+        #[cfg(feature = "std")]
+        if self.waiters.load(Ordering::SeqCst) > 0 {
+            self.wake();
+        }
+        version
+    }
+
+    /// Returns the number of values published so far.
+    #[inline]
+    fn version(&self) -> u64 {
+        self.seq.load(Ordering::SeqCst) / 2
+    }
+
+    /// Loads the latest value along with the version it was published as.
+    ///
+    /// The value is only taken if `seq` is even and unchanged around the
+    /// load, which means no publish was in flight, and is retried otherwise.
+    /// A publish is only a store between the two bumps, so a retry usually
+    /// succeeds right away, but a writer preempted mid-publish keeps `seq`
+    /// odd for as long as it is off the CPU. After `READ_SPINS` retries this
+    /// yields on every retry instead, so that on a shared core the writer
+    /// gets to finish rather than the reader burning its timeslice. Without
+    /// `std` there is nothing to yield to and it keeps spinning, so a read
+    /// from an interrupt that preempted a publish never returns.
+    #[inline]
+    fn load_versioned(&self) -> (Guard<Arc<M>>, u64) {
+        let (value, version, ()) = self.load_versioned_with(|_| ());
+        (value, version)
+    }
+
+    /// Does the work of `load_versioned`, also calling `also` right after
+    /// loading the value, so that whatever it loads comes from the same
+    /// publish as the value does.
+    #[inline]
+    fn load_versioned_with<T>(&self, also: impl Fn(&Self) -> T) -> (Guard<Arc<M>>, u64, T) {
+        let mut retries = 0;
+        loop {
+            let before = self.seq.load(Ordering::Acquire);
+            if before & 1 == 0 {
+                let value = self.latest.load();
+                let extra = also(self);
+                if self.seq.load(Ordering::Acquire) == before {
+                    return (value, before / 2, extra);
+                }
+            }
+            if retries < READ_SPINS {
+                retries += 1;
+                sync::spin_loop();
+            } else {
+                sync::yield_now();
+            }
+        }
+    }
+
+    /// Returns how long ago `published_at` was.
+    #[cfg(feature = "latency")]
+    fn age(&self, published_at: u64) -> Duration {
+        let now = self.epoch.elapsed().as_nanos() as u64;
+        Duration::from_nanos(now.saturating_sub(published_at))
+    }
+
+    /// Makes a reader a `seen` counter starting at `last_seen` and adds it to
+    /// the ones `Latest::reader_lags` reads, or returns `None` if that has
+    /// never been called.
+    #[cfg(feature = "std")]
+    fn track(&self, last_seen: u64) -> Option<Arc<AtomicU64>> {
+        let readers = self.seen.get()?;
+        let seen = Arc::new(AtomicU64::new(last_seen));
+        readers.lock().unwrap().push(sync::downgrade(&seen));
+        Some(seen)
+    }
+
+    #[cfg(feature = "std")]
+    fn wake(&self) {
+        let guard = self.lock.lock().unwrap();
+        self.cond.notify_all();
+        drop(guard);
+
+        #[cfg(feature = "async")]
+        {
+            let wakers = std::mem::take(&mut *self.wakers.lock().unwrap());
+            self.waiters.fetch_sub(wakers.len(), Ordering::SeqCst);
+            for waker in wakers {
+                waker.wake();
+            }
+        }
+    }
+
+    /// Sleeps until the version moves past `seen` or the writer is dropped.
+    ///
+    /// The version is checked `spins` times in a spin loop first, which
+    /// catches a value published right away without the writer having to
+    /// take `lock`. Returns `false` if `deadline` passed first. Spurious
+    /// wakeups go back to sleep for whatever is left until the deadline.
+    #[cfg(feature = "std")]
+    fn wait_past(&self, seen: u64, spins: u32, deadline: Option<Instant>) -> bool {
+        for _ in 0..spins {
+            if self.version() != seen || self.closed.load(Ordering::SeqCst) {
+                return true;
+            }
+            sync::spin_loop();
+        }
+        self.waiters.fetch_add(1, Ordering::SeqCst);
+        let mut guard = self.lock.lock().unwrap();
+        let mut woken = true;
+        while self.version() == seen && !self.closed.load(Ordering::SeqCst) {
+            guard = match deadline {
+                None => self.cond.wait(guard).unwrap(),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        woken = false;
+                        break;
+                    }
+                    self.cond.wait_timeout(guard, deadline - now).unwrap().0
+                }
+            };
+        }
+        drop(guard);
+        self.waiters.fetch_sub(1, Ordering::SeqCst);
+        woken
+    }
+
+    /// Sleeps until the writer is dropped.
+    #[cfg(feature = "std")]
+    fn wait_closed(&self) {
+        self.waiters.fetch_add(1, Ordering::SeqCst);
+        let mut guard = self.lock.lock().unwrap();
+        while !self.closed.load(Ordering::SeqCst) {
+            guard = self.cond.wait(guard).unwrap();
+        }
+        drop(guard);
+        self.waiters.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// The writer for a value that many readers want the newest copy of.
+///
+/// To let producers publish different concrete types, make `M` an
+/// `Arc<dyn Trait>`. Readers then get back a clone of that `Arc`, pointing at
+/// whatever it was published with, so there is nothing to downcast. There is
+/// no `Default` for it, so build the `Latest` with `with_initial`.
+///
+/// Unlike `SeqLatest`, nothing needs `M: Copy`, so owning payloads like
+/// `String` work as they are. Reads that hand back an `M` need `Clone`, and
+/// only the constructors that fill the ring without an initial value need
+/// `Default`.
+///
+/// `get` hands each reader its own clone, so the cheaper `M` is to clone the
+/// cheaper reads are. `bytes::Bytes` makes an ideal payload for messages
+/// straight off the network: cloning one bumps a reference count rather than
+/// copying the buffer, and its `Default` is an empty buffer that allocates
+/// nothing.
+///
+/// Its `Debug` output sums up the ring rather than printing every slot. The
+/// alternate form, `{:#?}`, adds the slots and their payloads.
+pub struct Latest<M> {
+    q: Vec<Arc<M>>,
+    shared: Arc<Shared<M>>,
+    index: usize,
+    /// How far `q` may shrink in `reclaim`, which is its starting length
+    /// unless changed with `set_min_capacity`.
+    min_cap: usize,
+    /// How far `q` may grow when every slot is held. Equal to its starting
+    /// length unless the `Latest` was made with `growable`.
+    max_cap: usize,
+    overflow: OverflowPolicy,
+    /// The version counting started from, which `reader` treats as seen. 0
+    /// unless set with `with_version`.
+    first_version: u64,
+    /// Zeroes a value before the ring lets go of it, if set with
+    /// `with_zero_on_reclaim`. A function pointer rather than a bound so that
+    /// only that constructor needs `M: Zeroize`.
+    #[cfg(feature = "zeroize")]
+    zero: Option<fn(&mut M)>,
+    /// How many slots `try_set` has found held and skipped over. The writer
+    /// is the only one touching it, so it needs no atomic.
+    #[cfg(feature = "metrics")]
+    retries: u64,
+    /// Whether `retries` and `stamps` are kept up to date, which a
+    /// `LatestBuilder` can turn off.
+    #[cfg(feature = "metrics")]
+    metrics: bool,
+    /// The version each slot's value was published as, kept alongside `q`
+    /// for `slot_versions`. 0 is the initial value or one that was never
+    /// published.
+    #[cfg(feature = "metrics")]
+    stamps: Vec<u64>,
+    /// The stamp the last publish overwrote, so that `set_and_take` can move
+    /// it along with the value it took out of that slot.
+    #[cfg(feature = "metrics")]
+    replaced_stamp: u64,
+    observers: Observers<M>,
+    /// Only set up once someone asks for a `HistoryReader`, since keeping it
+    /// up to date costs every `set` a couple of extra stores.
+    #[cfg(feature = "std")]
+    history: OnceLock<Arc<history::History<M>>>,
+}
+
+impl<M> Latest<M>
+where
+    M: Default + Clone,
+{
+    /// Creates a `Latest` with `cap` ring slots, all holding `M::default()`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cap` is less than 2. See `with_initial`.
+    pub fn new(cap: usize) -> Self {
+        Self::with_initial(cap, M::default())
+    }
+
+    /// Like `new`, but returns an error rather than panicking if `cap` is
+    /// less than 2, for a capacity that comes from configuration.
+    pub fn try_new(cap: usize) -> Result<Self, CapacityError> {
+        if cap < 2 {
+            return Err(CapacityError(cap));
+        }
+        Ok(Self::new(cap))
+    }
+
+    /// Creates a `Latest` like `new`, with `cap` rounded up to a power of two
+    /// so that moving on to the next slot is a mask rather than a division.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cap` is less than 2.
+    pub fn with_capacity_pow2(cap: usize) -> Self {
+        Self::new(cap.next_power_of_two())
+    }
+
+    /// Starts a `LatestBuilder` for a `Latest` of `M::default()`, to set
+    /// several options at once.
+    pub fn builder() -> LatestBuilder<M> {
+        LatestBuilder::new(M::default())
+    }
+
+    /// Creates a `Latest` like `new` whose ring grows instead of failing to
+    /// publish when readers are holding every slot.
+    ///
+    /// Each time `try_set` sweeps the whole ring without finding a free slot
+    /// it adds one more, up to `max_cap` slots. Slots are never given back, so
+    /// readers that keep hanging on to values cost memory for good.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cap` is less than 2 or `max_cap` is less than `cap`.
+    pub fn growable(cap: usize, max_cap: usize) -> Self {
+        assert!(
+            max_cap >= cap,
+            "a Latest cannot grow to {} slots from {}",
+            max_cap,
+            cap
+        );
+        let mut latest = Self::new(cap);
+        latest.max_cap = max_cap;
+        latest
+    }
+}
+
+impl<M> Latest<M>
+where
+    M: Clone,
+{
+    /// Creates a `Latest` with `cap` ring slots, all holding a clone of
+    /// `init`, for message types without a sensible `Default`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cap` is less than 2. The published value always keeps its
+    /// slot pinned through the shared cell, so the writer needs at least one
+    /// more slot to write the next value into before swapping it in.
+    pub fn with_initial(cap: usize, init: M) -> Self {
+        assert!(cap >= 2, "a Latest needs at least 2 slots, got {}", cap);
+        let mut q: Vec<Arc<M>> = Vec::with_capacity(cap);
+        for _ in 1..cap {
+            q.push(Arc::new(init.clone()));
+        }
+        q.push(Arc::new(init));
+        Self::from_ring(q, 0)
+    }
+
+    /// Creates a reader along with a copy of the current value, for a
+    /// subscriber joining after values have already been published.
+    ///
+    /// The reader counts that value as seen, so `get_if_changed` and
+    /// `wait_new` only report what is published after it. The writer is
+    /// borrowed throughout, so nothing can be published in between.
+    ///
+    /// The value is a plain `M` rather than an `Option<M>`: a `Latest` always
+    /// holds a current value, so there is no case in which it would be `None`.
+    pub fn subscribe(&self) -> (LatestReader<M>, M) {
+        let reader = LatestReader::new(sync::downgrade(&self.shared), self.shared.version());
+        (reader, (*self.q[self.index]).clone())
+    }
+
+    /// Publishes a new value by letting `f` edit a free ring slot in place,
+    /// rather than building a whole message to move in.
+    ///
+    /// `f` sees whatever that slot held last, which is an earlier value but
+    /// not necessarily the latest one, so it should overwrite everything it
+    /// doesn't mean to keep. If every slot is held and a `growable` ring grows
+    /// instead, the new slot starts out as a clone of the latest value.
+    ///
+    /// # Panics
+    ///
+    /// Panics like `set` if every ring slot is held by a reader.
+    pub fn set_with(&mut self, f: impl FnOnce(&mut M)) {
+        let mut f = Some(f);
+        if self.publish_with(|slot| f.take().unwrap()(slot)).is_some() {
+            return;
+        }
+        match self.overflow {
+            OverflowPolicy::Overwrite if self.q.len() < self.max_cap => {}
+            OverflowPolicy::Drop => return,
+            _ => panic!("every slot in the ring is held by a reader"),
+        }
+        let mut msg = (*self.q[self.index]).clone();
+        f.take().unwrap()(&mut msg);
+        self.q.push(Arc::new(msg));
+        self.index = self.q.len() - 1;
+        self.publish_current();
+    }
+}
+
+impl<M> Latest<M> {
+    /// Creates a `Latest` whose ring holds `values` in order, with the last
+    /// of them as the current value, for tests that need the ring in a known
+    /// state.
+    ///
+    /// The values count as published one after the other, so the version
+    /// starts at `values.len()` and a new reader's `get_if_changed` reports
+    /// the last one. Returns an error if there are fewer than 2 values.
+    pub fn from_values(values: Vec<M>) -> Result<Self, CapacityError> {
+        let len = values.len();
+        if len < 2 {
+            return Err(CapacityError(len));
+        }
+        // Only the metrics feature has anything to stamp afterwards.
+        #[cfg_attr(not(feature = "metrics"), allow(unused_mut))]
+        let mut latest = Self::from_ring(values.into_iter().map(Arc::new).collect(), len - 1);
+        latest
+            .shared
+            .seq
+            .store((len as u64).wrapping_mul(2), Ordering::SeqCst);
+        #[cfg(feature = "metrics")]
+        {
+            latest.stamps = (1..=len as u64).collect();
+        }
+        Ok(latest)
+    }
+
+    /// Wraps a ring of at least 2 slots, with the value at `index` as the
+    /// current one, in a `Latest` that can't grow past it.
+    fn from_ring(q: Vec<Arc<M>>, index: usize) -> Self {
+        let cap = q.len();
+        let shared = Arc::new(Shared::new(q[index].clone()));
+
+        Self {
+            q,
+            shared,
+            index,
+            min_cap: cap,
+            max_cap: cap,
+            overflow: OverflowPolicy::Overwrite,
+            first_version: 0,
+            #[cfg(feature = "zeroize")]
+            zero: None,
+            #[cfg(feature = "metrics")]
+            retries: 0,
+            #[cfg(feature = "metrics")]
+            metrics: true,
+            #[cfg(feature = "metrics")]
+            stamps: alloc::vec![0; cap],
+            #[cfg(feature = "metrics")]
+            replaced_stamp: 0,
+            observers: Observers(Vec::new()),
+            #[cfg(feature = "std")]
+            history: OnceLock::new(),
+        }
+    }
+
+    #[inline]
+    pub fn reader(&self) -> LatestReader<M> {
+        LatestReader::new(sync::downgrade(&self.shared), self.first_version)
+    }
+
+    /// Creates `n` independent readers at once, each as if from `reader`.
+    pub fn readers(&self, n: usize) -> Vec<LatestReader<M>> {
+        (0..n).map(|_| self.reader()).collect()
+    }
+
+    /// Returns how many `LatestReader`s are currently alive.
+    ///
+    /// Every reader holds exactly one `Weak` to the shared state, so this is
+    /// `Arc::weak_count` of that state. The writer and any `StrongReader`s
+    /// hold strong references instead and are not counted. The count is a
+    /// snapshot and may be stale by the time it is returned if other threads
+    /// are cloning or dropping readers.
+    #[inline]
+    pub fn reader_count(&self) -> usize {
+        sync::weak_count(&self.shared)
+    }
+
+    /// Returns the number of values published so far, the same count
+    /// `LatestReader::version` returns.
+    #[must_use]
+    #[inline]
+    pub fn current_version(&self) -> u64 {
+        self.shared.version()
+    }
+
+    /// Returns how many slots the ring has, which only changes for a
+    /// `growable` one.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.q.len()
+    }
+
+    /// Sets how small `reclaim` may shrink the ring.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min_cap` is less than 2.
+    pub fn set_min_capacity(&mut self, min_cap: usize) {
+        assert!(
+            min_cap >= 2,
+            "a Latest needs at least 2 slots, got {}",
+            min_cap
+        );
+        self.min_cap = min_cap;
+    }
+
+    /// Drops free slots off the end of the ring, down to the minimum capacity,
+    /// returning how many were dropped.
+    ///
+    /// This stops at the first slot from the end that is still held, which
+    /// always includes the one holding the current value. After a burst has
+    /// grown a `growable` ring, that value usually sits at the end until the
+    /// next `set` wraps the writer back round to the start.
+    pub fn reclaim(&mut self) -> usize {
+        let mut dropped = 0;
+        while self.q.len() > self.min_cap {
+            let last = self.q.len() - 1;
+            if last == self.index {
+                break;
+            }
+            #[cfg(feature = "std")]
+            let recorded = self.history.get().map(|history| history.forget(last));
+            if Arc::strong_count(&self.q[last]) != 1 {
+                #[cfg(feature = "std")]
+                if let (Some(history), Some(true)) = (self.history.get(), recorded) {
+                    history.restore(last, &self.q[last]);
+                }
+                break;
+            }
+            #[cfg(feature = "zeroize")]
+            self.scrub(last);
+            self.q.pop();
+            #[cfg(feature = "metrics")]
+            self.stamps.pop();
+            dropped += 1;
+        }
+        dropped
+    }
+
+    /// Returns how many times publishing found a ring slot still held by a
+    /// reader and moved on to the next, over the lifetime of this `Latest`.
+    ///
+    /// A count that keeps climbing means readers hold on to values long
+    /// enough that the ring is worth making bigger. It stays 0 if a
+    /// `LatestBuilder` turned metrics off.
+    #[cfg(feature = "metrics")]
+    #[inline]
+    pub fn retry_count(&self) -> u64 {
+        self.retries
+    }
+
+    /// Returns the version each ring slot's value was published as, in ring
+    /// order, with 0 for slots holding the initial value or a reset one.
+    ///
+    /// Next to `free_slots`, this shows which old values readers are keeping
+    /// alive and how far behind they are. Every slot reads 0 if a
+    /// `LatestBuilder` turned metrics off.
+    #[cfg(feature = "metrics")]
+    pub fn slot_versions(&self) -> Vec<u64> {
+        self.stamps.clone()
+    }
+
+    /// Returns how many versions behind the latest each tracked
+    /// `LatestReader` is, in the order they started being tracked.
+    ///
+    /// A reader counts as having caught up with the values `get_if_changed`,
+    /// `poll_coalesced`, `wait_new` and their kin return, which mark them as
+    /// seen; `get` and the other plain reads don't, so a reader using only
+    /// those falls further behind with every publish.
+    ///
+    /// Readers are only tracked once this has been called, so that making
+    /// one costs nothing extra otherwise. From then on every new reader is
+    /// tracked, and one made before is tracked from the next time it marks a
+    /// value as seen. Dropped readers are cleared out here.
+    #[cfg(feature = "std")]
+    pub fn reader_lags(&self) -> Vec<u64> {
+        let current = self.current_version();
+        let mut lags = Vec::new();
+        self.shared
+            .seen
+            .get_or_init(Mutex::default)
+            .lock()
+            .unwrap()
+            .retain(|seen| match seen.upgrade() {
+                Some(seen) => {
+                    lags.push(versions_since(current, seen.load(Ordering::Relaxed)));
+                    true
+                }
+                None => false,
+            });
+        lags
+    }
+
+    /// Returns how many ring slots no reader is holding, and so could take
+    /// the next `set` right away.
+    ///
+    /// The current value is always held by the shared cell, so this is at
+    /// most `cap - 1`. Slots still held by a `HistoryReader`'s record are
+    /// counted as held too. Readers pick up and drop values concurrently, so
+    /// the count is a racy snapshot, useful for spotting an undersized ring
+    /// that keeps reporting zero rather than for deciding anything.
+    pub fn free_slots(&self) -> usize {
+        self.q
+            .iter()
+            .filter(|slot| Arc::strong_count(slot) == 1)
+            .count()
+    }
+
+    /// Masks rather than divides when the ring length is a power of two,
+    /// which `with_capacity_pow2` makes sure of. Checking the length each time
+    /// keeps this right as a `growable` ring changes size, and costs a couple
+    /// of instructions against the tens a division takes.
+    #[inline]
+    fn next_idx(&self) -> usize {
+        let len = self.q.len();
+        if len.is_power_of_two() {
+            (self.index + 1) & (len - 1)
+        } else {
+            (self.index + 1) % len
+        }
+    }
+
+    /// Publishes `msg` into the next ring slot that no reader is holding.
+    ///
+    /// The whole ring is swept at most once. If every slot is still held by a
+    /// reader, a `growable` ring gets a new slot for `msg`; otherwise, or once
+    /// it is at its maximum, the message is handed back in the error. The
+    /// `OverflowPolicy` set with `with_overflow` can change that to dropping
+    /// `msg`, or to never growing.
+    #[inline]
+    pub fn try_set(&mut self, msg: M) -> Result<(), SetError<M>> {
+        self.try_replace(msg).map(drop)
+    }
+
+    /// Does the work of `try_set`, saying what became of `msg`.
+    #[inline]
+    fn try_replace(&mut self, msg: M) -> Result<Replaced<M>, SetError<M>> {
+        let mut msg = Some(msg);
+        if let Some(stale) = self.publish_with(|slot| mem::replace(slot, msg.take().unwrap())) {
+            return Ok(Replaced::Stale(stale));
+        }
+        // `publish_with` only calls the closure once it has a slot.
+        self.on_overflow(msg.unwrap())
+    }
+
+    /// Does what the overflow policy says with `msg` once every slot has
+    /// turned out to be held.
+    #[cold]
+    #[inline(never)]
+    fn on_overflow(&mut self, msg: M) -> Result<Replaced<M>, SetError<M>> {
+        match self.overflow {
+            OverflowPolicy::Overwrite if self.q.len() < self.max_cap => {
+                self.q.push(Arc::new(msg));
+                self.index = self.q.len() - 1;
+                self.publish_current();
+                Ok(Replaced::Grew)
+            }
+            OverflowPolicy::Drop => Ok(Replaced::Dropped),
+            _ => Err(SetError(msg)),
+        }
+    }
+
+    /// Sweeps the ring at most once for a slot no reader is holding, lets
+    /// `write` fill it in and publishes it, handing back what `write`
+    /// returned. Returns `None` without calling `write` if every slot is held.
+    ///
+    /// Only the first slot is tried inline. The next one is nearly always
+    /// free, and keeping the sweep out of line keeps `set` small enough to
+    /// inline into the writer's loop.
+    #[inline]
+    fn publish_with<R, W>(&mut self, write: W) -> Option<R>
+    where
+        W: FnOnce(&mut M) -> R,
+    {
+        match self.publish_next(write) {
+            Ok(out) => Some(out),
+            Err(write) => self.publish_with_sweep(write),
+        }
+    }
+
+    /// The rest of `publish_with`'s sweep, after the first slot was held.
+    #[cold]
+    #[inline(never)]
+    fn publish_with_sweep<R, W>(&mut self, mut write: W) -> Option<R>
+    where
+        W: FnOnce(&mut M) -> R,
+    {
+        // Without `std` the cell can be keeping slots it has swapped out
+        // until no load could still be reaching for them.
+        #[cfg(all(not(feature = "std"), not(loom)))]
+        self.shared.latest.reclaim();
+        for _ in 1..self.q.len() {
+            write = match self.publish_next(write) {
+                Ok(out) => return Some(out),
+                Err(write) => write,
+            };
+        }
+        None
+    }
+
+    /// Moves on to the next slot and, if no reader is holding it, lets
+    /// `write` fill it in and publishes it. Hands `write` back otherwise.
+    #[inline]
+    fn publish_next<R, W>(&mut self, write: W) -> Result<R, W>
+    where
+        W: FnOnce(&mut M) -> R,
+    {
+        self.index = self.next_idx();
+        #[cfg(feature = "std")]
+        let recorded = self.history.get().map(|history| history.forget(self.index));
+        #[cfg(feature = "zeroize")]
+        let zero = self.zero;
+        if let Some(slot) = Arc::get_mut(&mut self.q[self.index]) {
+            #[cfg(feature = "zeroize")]
+            if let Some(zero) = zero {
+                zero(slot);
+            }
+            let out = write(slot);
+            self.publish_current();
+            return Ok(out);
+        }
+        #[cfg(feature = "metrics")]
+        if self.metrics {
+            self.retries = self.retries.wrapping_add(1);
+        }
+        #[cfg(feature = "std")]
+        if let (Some(history), Some(true)) = (self.history.get(), recorded) {
+            history.restore(self.index, &self.q[self.index]);
+        }
+        Err(write)
+    }
+
+    /// Publishes the value in the slot at `index`.
+    #[inline]
+    fn publish_current(&mut self) {
+        let _version = self.shared.publish(self.q[self.index].clone());
+        #[cfg(feature = "metrics")]
+        {
+            // Growing pushes the new slot onto `q` just before publishing it.
+            self.stamps.resize(self.q.len(), 0);
+            if self.metrics {
+                self.replaced_stamp = mem::replace(&mut self.stamps[self.index], _version);
+            }
+        }
+        #[cfg(feature = "std")]
+        if let Some(history) = self.history.get() {
+            history.record(self.index, &self.q[self.index], _version);
+        }
+        let published = &self.q[self.index];
+        self.observers.0.retain(|observer| observer(published));
+        #[cfg(feature = "debug-invariants")]
+        self.check_invariants();
+    }
+
+    /// Panics unless the ring and the shared cell agree once a publish is
+    /// done: `index` is in range, the published value is the one at `index`
+    /// and no other slot holds it, and no publish is left in flight.
+    #[cfg(feature = "debug-invariants")]
+    fn check_invariants(&self) {
+        let len = self.q.len();
+        assert!(
+            self.index < len,
+            "index {} is past the end of a ring of {}",
+            self.index,
+            len
+        );
+        assert!(
+            (2..=self.max_cap).contains(&len),
+            "a ring of {} is outside 2 to {}",
+            len,
+            self.max_cap
+        );
+        let latest = self.shared.latest.load();
+        let published = self
+            .q
+            .iter()
+            .filter(|slot| Arc::ptr_eq(slot, &latest))
+            .count();
+        assert_eq!(
+            published, 1,
+            "the published value is in {} slots",
+            published
+        );
+        assert!(
+            Arc::ptr_eq(&self.q[self.index], &latest),
+            "the published value is not the one at index {}",
+            self.index
+        );
+        assert!(
+            self.shared.seq.load(Ordering::SeqCst) & 1 == 0,
+            "a publish was left in flight"
+        );
+        #[cfg(feature = "metrics")]
+        assert_eq!(self.stamps.len(), len, "slot versions are out of step");
+    }
+
+    /// Leaves a publish half done, as a writer that panicked partway through
+    /// would, so that tests can check `check_invariants` catches it.
+    #[cfg(feature = "debug-invariants")]
+    #[doc(hidden)]
+    pub fn debug_leave_publish_in_flight(&mut self) {
+        self.shared.seq.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Registers `f` to be called with every value published from now on.
+    ///
+    /// Callbacks run synchronously on the writer's thread, in the order they
+    /// were registered, right after each value is swapped in and readers are
+    /// woken. Whatever they cost is added to every `set`, so they should be
+    /// cheap, like bumping a counter or handing the value to a logger. A
+    /// panicking callback panics the writer.
+    ///
+    /// `f` has to be `Sync` as well as `Send` so that `Latest` stays `Sync`.
+    /// It is kept for as long as the `Latest`, which has no lifetime to tie
+    /// a borrow to, so unlike `M` it can't borrow from a scoped thread's
+    /// surroundings.
+    pub fn on_publish(&mut self, f: impl Fn(&M) + Send + Sync + 'static) {
+        self.observe(move |msg| {
+            f(msg);
+            true
+        });
+    }
+
+    /// Like `on_publish`, but `f` is dropped once it returns `false`.
+    fn observe(&mut self, f: impl Fn(&M) -> bool + Send + Sync + 'static) {
+        self.observers.0.push(Box::new(f));
+    }
+
+    /// Sets what publishing does when every ring slot is held by a reader.
+    ///
+    /// The default is `OverflowPolicy::Overwrite`. Meant to be chained onto
+    /// the constructor, like `LatestReader::with_spins`.
+    pub fn with_overflow(mut self, policy: OverflowPolicy) -> Self {
+        self.overflow = policy;
+        self
+    }
+
+    /// Makes the ring zero each value it is done with before reusing its slot
+    /// or dropping it, so that sensitive payloads don't linger in memory
+    /// after readers have moved on.
+    ///
+    /// This covers every slot `set` and its variants overwrite, and the ones
+    /// `reclaim`, `reclaim_stale` and shrinking `resize` let go of. The value
+    /// `set_and_take` hands back is the caller's to clear. Dropping the
+    /// `Latest` zeroes every slot only the ring holds, but the current value
+    /// and any a reader is still holding are freed as they are, whenever the
+    /// last reference to them goes. `set_with` is handed the zeroed value to
+    /// fill in rather than the old one.
+    #[cfg(feature = "zeroize")]
+    pub fn with_zero_on_reclaim(mut self) -> Self
+    where
+        M: Zeroize,
+    {
+        self.zero = Some(M::zeroize);
+        self
+    }
+
+    /// Zeroes the value at `idx` if `with_zero_on_reclaim` asked for it and
+    /// nothing but the ring holds it.
+    #[cfg(feature = "zeroize")]
+    fn scrub(&mut self, idx: usize) {
+        if let (Some(zero), Some(value)) = (self.zero, Arc::get_mut(&mut self.q[idx])) {
+            zero(value);
+        }
+    }
+
+    /// Starts counting versions from `version` rather than 0, for a pipeline
+    /// that carries its versions on across restarts.
+    ///
+    /// Versions wrap round to 0 after 2^63 - 1, and `version` is taken modulo
+    /// that. Readers made after this treat `version` as seen, as they would
+    /// 0 otherwise, but ones made before still count from 0 and can take
+    /// newer versions for older ones, so chain it onto the constructor.
+    pub fn with_version(mut self, version: u64) -> Self {
+        self.first_version = version & MAX_VERSION;
+        self.shared
+            .seq
+            .store(self.first_version * 2, Ordering::SeqCst);
+        self
+    }
+
+    /// Marks this `Latest` as failed, so that `LatestReader::try_get` returns
+    /// `ReadError::Poisoned` from then on, like a poisoned `Mutex`.
+    ///
+    /// Only the first reason is kept. Publishing still works afterwards, and
+    /// plain reads like `get` keep returning the latest value, so stages that
+    /// should fail fast have to read through `try_get` or check
+    /// `poison_reason`. Readers blocked in `wait_new` are not woken; dropping
+    /// the writer once it has given up does that.
+    #[cfg(feature = "std")]
+    pub fn poison(&mut self, reason: impl Into<String>) {
+        let _ = self.shared.poison.set(reason.into());
+    }
+
+    /// Like `try_set`, but panics if every ring slot is held by a reader,
+    /// unless the `OverflowPolicy` is `Drop`.
+    #[inline]
+    pub fn set(&mut self, msg: M) {
+        self.set_published(msg);
+    }
+
+    /// Does the work of `set`, returning whether `msg` was published rather
+    /// than dropped by the overflow policy.
+    #[inline]
+    fn set_published(&mut self, msg: M) -> bool {
+        match self.try_replace(msg) {
+            Ok(replaced) => !matches!(replaced, Replaced::Dropped),
+            Err(_) => panic!("every slot in the ring is held by a reader"),
+        }
+    }
+
+    /// Publishes `msg` only if the current version is `expected_version`,
+    /// returning the version `msg` was published as, or the actual current
+    /// version in `CasError::Moved` otherwise. If every ring slot is held
+    /// and the `OverflowPolicy` is `Drop`, it returns `CasError::Dropped`.
+    ///
+    /// This lets a writer that decided what to publish from what it last saw
+    /// back off if something else was published since. Only this `Latest`
+    /// publishes to its ring, so here that is just a check before `set`;
+    /// `SharedWriter::compare_and_set` is the one that races.
+    ///
+    /// # Panics
+    ///
+    /// Panics like `set` if every ring slot is held by a reader, unless the
+    /// `OverflowPolicy` is `Drop`.
+    pub fn compare_and_set(&mut self, expected_version: u64, msg: M) -> Result<u64, CasError> {
+        let actual = self.current_version();
+        if actual != expected_version {
+            return Err(CasError::Moved(actual));
+        }
+        if !self.set_published(msg) {
+            return Err(CasError::Dropped);
+        }
+        Ok(self.current_version())
+    }
+
+    /// Publishes `msg` and hands back the value it replaced as the latest, if
+    /// no reader is still holding that one.
+    ///
+    /// This lets large payloads be recycled rather than reallocated. The slot
+    /// the old value leaves is filled with the stale value `msg` overwrote, so
+    /// the ring never needs a placeholder. Returns `None` if a reader or a
+    /// `HistoryReader` record still holds the old value, or if a `growable`
+    /// ring had to grow for `msg` or the overflow policy dropped it, and so
+    /// nothing was overwritten.
+    ///
+    /// # Panics
+    ///
+    /// Panics like `set` if every ring slot is held by a reader.
+    pub fn set_and_take(&mut self, msg: M) -> Option<M> {
+        let prev = self.index;
+        let stale = match self.try_replace(msg) {
+            Ok(Replaced::Stale(stale)) => stale,
+            Ok(_) => return None,
+            Err(_) => panic!("every slot in the ring is held by a reader"),
+        };
+        #[cfg(feature = "std")]
+        let recorded = self.history.get().map(|history| history.forget(prev));
+        if let Some(slot) = Arc::get_mut(&mut self.q[prev]) {
+            // The stale value keeps its version when it moves, which the
+            // publish of `msg` replaced in its slot's stamp.
+            #[cfg(feature = "metrics")]
+            if self.metrics {
+                self.stamps[prev] = self.replaced_stamp;
+            }
+            return Some(mem::replace(slot, stale));
+        }
+        #[cfg(feature = "std")]
+        if let (Some(history), Some(true)) = (self.history.get(), recorded) {
+            history.restore(prev, &self.q[prev]);
+        }
+        None
+    }
+
+    /// Publishes only the last of `msgs`, as a single new version.
+    ///
+    /// The rest are dropped without readers ever seeing them, which is all a
+    /// reader of the latest value would have seen of a burst anyway. Nothing
+    /// is published if `msgs` is empty.
+    ///
+    /// # Panics
+    ///
+    /// Panics like `set` if every ring slot is held by a reader.
+    #[inline]
+    pub fn set_many<I>(&mut self, msgs: I)
+    where
+        I: IntoIterator<Item = M>,
+    {
+        if let Some(msg) = msgs.into_iter().last() {
+            self.set(msg);
+        }
+    }
+}
+
+impl<M> Latest<M>
+where
+    M: PartialEq,
+{
+    /// Publishes `msg` unless it equals the current latest value, returning
+    /// whether it was published. It also returns `false` if every ring slot
+    /// was held and the `OverflowPolicy` is `Drop`.
+    ///
+    /// Skipping an equal value leaves the version alone, so readers waiting
+    /// on a change are not woken for nothing. The comparison is against the
+    /// writer's own slot for the latest value, so it neither touches the
+    /// shared cell nor clones anything.
+    ///
+    /// # Panics
+    ///
+    /// Panics like `set` if every ring slot is held by a reader.
+    #[inline]
+    pub fn set_if_changed(&mut self, msg: M) -> bool {
+        if *self.q[self.index] == msg {
+            return false;
+        }
+        self.set_published(msg)
+    }
+}
+
+impl<M> Latest<M>
+where
+    M: Default,
+{
+    /// Resets every slot that nothing else is holding, other than the one
+    /// with the current value, to `M::default()`, returning how many it reset.
+    ///
+    /// A slot keeps the last value written into it until `set` comes round to
+    /// it again, so a writer that publishes rarely can keep large stale
+    /// payloads alive long after every reader has moved on. This drops them
+    /// now rather than on the next lap. The ring keeps its length, and slots
+    /// a `HistoryReader` is keeping a record of are left alone.
+    pub fn reclaim_stale(&mut self) -> usize {
+        let current = self.index;
+        let mut reset = 0;
+        for (idx, slot) in self.q.iter_mut().enumerate() {
+            if idx == current {
+                continue;
+            }
+            if let Some(value) = Arc::get_mut(slot) {
+                #[cfg(feature = "zeroize")]
+                if let Some(zero) = self.zero {
+                    zero(value);
+                }
+                *value = M::default();
+                #[cfg(feature = "metrics")]
+                {
+                    self.stamps[idx] = 0;
+                }
+                reset += 1;
+            }
+        }
+        reset
+    }
+
+    /// Changes the ring to `new_cap` slots, keeping the current value and
+    /// version.
+    ///
+    /// Growing appends slots holding `M::default()`. Shrinking drops slots
+    /// off the end, and fails without changing anything if any of them is
+    /// held by a reader or holds the current value. Either way `new_cap`
+    /// becomes the size `reclaim` shrinks back down to, and a `growable`
+    /// ring can still grow as far as it could before, or to `new_cap` if
+    /// that is further. Slots added past that are left out of any
+    /// `HistoryReader`'s history.
+    pub fn resize(&mut self, new_cap: usize) -> Result<(), ResizeError> {
+        if new_cap < 2 {
+            return Err(ResizeError::TooSmall);
+        }
+        let len = self.q.len();
+        if new_cap > len {
+            self.q.resize_with(new_cap, || Arc::new(M::default()));
+        } else if new_cap < len {
+            #[cfg(feature = "std")]
+            let recorded: Vec<bool> = (new_cap..len)
+                .map(|idx| {
+                    self.history
+                        .get()
+                        .is_some_and(|history| history.forget(idx))
+                })
+                .collect();
+            let held = self.index >= new_cap
+                || self.q[new_cap..]
+                    .iter()
+                    .any(|slot| Arc::strong_count(slot) != 1);
+            if held {
+                #[cfg(feature = "std")]
+                if let Some(history) = self.history.get() {
+                    for (idx, recorded) in (new_cap..len).zip(recorded) {
+                        if recorded {
+                            history.restore(idx, &self.q[idx]);
+                        }
+                    }
+                }
+                return Err(ResizeError::Pinned);
+            }
+            #[cfg(feature = "zeroize")]
+            for idx in new_cap..len {
+                self.scrub(idx);
+            }
+            self.q.truncate(new_cap);
+        }
+        #[cfg(feature = "metrics")]
+        self.stamps.resize(new_cap, 0);
+        self.min_cap = new_cap;
+        self.max_cap = self.max_cap.max(new_cap);
+        Ok(())
+    }
+}
+
+/// Makes an independent `Latest` seeded with the current value, for forking
+/// state off into a pipeline of its own.
+///
+/// The clone gets a fresh ring of the same capacity with every slot holding
+/// the current value, and keeps the same growth limits and overflow policy.
+/// It shares nothing else: readers of the original are not connected to it
+/// and neither see its values nor count towards its `reader_count`. Its
+/// version starts again from 0, and `on_publish` callbacks are not carried
+/// over.
+impl<M: Clone> Clone for Latest<M> {
+    fn clone(&self) -> Self {
+        let mut latest = Self::with_initial(self.q.len(), (*self.q[self.index]).clone());
+        latest.min_cap = self.min_cap;
+        latest.max_cap = self.max_cap;
+        latest.overflow = self.overflow;
+        #[cfg(feature = "zeroize")]
+        {
+            latest.zero = self.zero;
+        }
+        latest
+    }
+}
+
+impl<M> Drop for Latest<M> {
+    fn drop(&mut self) {
+        #[cfg(feature = "zeroize")]
+        for idx in 0..self.q.len() {
+            self.scrub(idx);
+        }
+        self.shared.closed.store(true, Ordering::SeqCst);
+        #[cfg(feature = "std")]
+        self.shared.wake();
+        #[cfg(feature = "std")]
+        if let Some(history) = self.history.get() {
+            history.close();
+        }
+    }
+}
+
+/// A callback registered with `Latest::on_publish`, which returns whether to
+/// keep it for the next publish.
+type Observer<M> = Box<dyn Fn(&M) -> bool + Send + Sync>;
+
+/// The callbacks registered with `Latest::on_publish`, wrapped so that
+/// `Latest` can still derive `Debug`.
+struct Observers<M>(Vec<Observer<M>>);
+
+impl<M: fmt::Debug> fmt::Debug for Latest<M> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let full = f.alternate();
+        let mut d = f.debug_struct("Latest");
+        d.field("capacity", &self.capacity())
+            .field("version", &self.current_version())
+            .field("free_slots", &self.free_slots())
+            .field("readers", &self.reader_count());
+        if full {
+            d.field("index", &self.index).field("q", &self.q);
+        }
+        d.finish()
+    }
+}
+
+impl<M> fmt::Debug for Observers<M> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Observers")
+            .field("len", &self.0.len())
+            .finish()
+    }
+}
+
+/// What became of a value handed to `Latest::try_replace`.
+enum Replaced<M> {
+    /// It was published over this stale value.
+    Stale(M),
+    /// It was published into a slot the ring grew for it.
+    Grew,
+    /// `OverflowPolicy::Drop` dropped it unpublished.
+    Dropped,
+}
+
+/// What a `Latest` does with a new value when readers are holding every ring
+/// slot, as set with `Latest::with_overflow`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// Grow a `growable` ring for the value, and otherwise have `try_set`
+    /// hand it back and `set` panic.
+    #[default]
+    Overwrite,
+    /// Drop the value without publishing it, from `try_set` and `set` alike,
+    /// since readers that far behind would likely never see it anyway.
+    Drop,
+    /// Never grow, and have `try_set` hand the value back and `set` panic,
+    /// so the producer hears about every saturated ring.
+    Error,
+}
+
+/// The error returned by `Latest::try_set` when every ring slot is held by a
+/// reader. It hands back the message that could not be published.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SetError<M>(pub M);
+
+impl<M> fmt::Display for SetError<M> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("every slot in the ring is held by a reader")
+    }
+}
+
+#[cfg(feature = "std")]
+impl<M: fmt::Debug> Error for SetError<M> {}
+
+/// The error returned by `Latest::compare_and_set` when the value was not
+/// published.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CasError {
+    /// The version had moved on from the expected one to this.
+    Moved(u64),
+    /// The version matched, but every ring slot was held and the
+    /// `OverflowPolicy` is `Drop`, so the value was dropped.
+    Dropped,
+}
+
+impl fmt::Display for CasError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CasError::Moved(actual) => {
+                write!(f, "the latest version was {}, not the one expected", actual)
+            }
+            CasError::Dropped => f.write_str("every slot was held, so the value was dropped"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for CasError {}
+
+/// The error returned by `Latest::try_new` for a ring too small to publish
+/// into. It holds the capacity that was asked for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CapacityError(pub usize);
+
+impl fmt::Display for CapacityError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a Latest needs at least 2 slots, got {}", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for CapacityError {}
+
+/// The error returned by `Latest::resize`.
+///
+/// More variants may be added as resizing learns to fail in new ways.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ResizeError {
+    /// The new capacity was less than 2.
+    TooSmall,
+    /// A slot that shrinking would drop is held by a reader or holds the
+    /// current value.
+    Pinned,
+}
+
+impl fmt::Display for ResizeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ResizeError::TooSmall => f.write_str("a Latest needs at least 2 slots"),
+            ResizeError::Pinned => f.write_str("a slot to be dropped is still held"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for ResizeError {}
+
+/// The writing half of a `Latest`, as returned by `channel`.
+pub type LatestWriter<M> = Latest<M>;
+
+/// Creates a `Latest` with `cap` ring slots, returning the writer along with a
+/// first reader in the style of `std::sync::mpsc::channel`.
+///
+/// The writer owns the ring and can be moved into the producing thread on its
+/// own; more readers can be made from either half.
+///
+/// Nothing here needs `M: 'static`, so under `std::thread::scope` both halves
+/// can be lent to scoped threads and publish values borrowed from the stack.
+pub fn channel<M>(cap: usize) -> (LatestWriter<M>, LatestReader<M>)
+where
+    M: Default + Clone,
+{
+    let writer = Latest::new(cap);
+    let reader = writer.reader();
+    (writer, reader)
+}
+
+/// Reads each of `readers` once, in order, as `get` would, for a step that
+/// needs the latest value of several `Latest`s such as one per sensor.
+///
+/// Each value is whole and consistent on its own, but the set is not one
+/// snapshot: the reads follow one another, so a writer publishing in between
+/// can leave an earlier value older than a later one. Compare versions
+/// through `get_versioned` where that matters.
+pub fn snapshot_all<M: Clone>(readers: &[LatestReader<M>]) -> Vec<Option<M>> {
+    readers.iter().map(LatestReader::get).collect()
+}
+
+/// A reader of a `Latest`, made with `Latest::reader` or by cloning another.
+///
+/// There is no unsafe code behind `Send` and `Sync` here or on `Latest`;
+/// both follow from the shared cell, which hands the same `M` to several
+/// threads at once. So a reader can move to or be shared with another thread
+/// whenever `M` is `Send + Sync`, and so can the writer. `tests/latest.rs`
+/// checks this for `Message`.
+#[derive(Debug)]
+pub struct LatestReader<M> {
+    shared: Weak<Shared<M>>,
+    /// The version of the last value returned by `get_if_changed`.
+    last_seen: u64,
+    /// How many times `wait_new` checks for a new value before sleeping.
+    #[cfg(feature = "std")]
+    spins: u32,
+    /// How long the last value `take_new` took had been published for.
+    #[cfg(feature = "latency")]
+    latency: Option<Duration>,
+    /// A copy of `last_seen` for the writer's `reader_lags`, once it tracks
+    /// this reader.
+    #[cfg(feature = "std")]
+    seen: Option<Arc<AtomicU64>>,
+}
+
+/// How many times `wait_new` checks for a new value before sleeping, unless
+/// changed with `LatestReader::with_spins`. This is a few microseconds at
+/// most, short enough not to matter to a reader that ends up sleeping anyway.
+#[cfg(feature = "std")]
+const DEFAULT_SPINS: u32 = 100;
+
+// `Latest::reader_count` counts the `Weak`s, so cloning and dropping the
+// `Weak` keeps it accurate without a count or a `Drop` of our own.
+impl<M> Clone for LatestReader<M> {
+    fn clone(&self) -> Self {
+        // Only std has anything to copy over afterwards.
+        #[cfg_attr(not(feature = "std"), allow(unused_mut))]
+        let mut reader = Self::new(self.shared.clone(), self.last_seen);
+        #[cfg(feature = "std")]
+        {
+            reader.spins = self.spins;
+        }
+        #[cfg(feature = "latency")]
+        {
+            reader.latency = self.latency;
+        }
+        reader
+    }
+}
+
+impl<M> LatestReader<M> {
+    fn new(shared: Weak<Shared<M>>, last_seen: u64) -> Self {
+        #[cfg(feature = "std")]
+        let seen = shared.upgrade().and_then(|shared| shared.track(last_seen));
+        Self {
+            shared,
+            last_seen,
+            #[cfg(feature = "std")]
+            spins: DEFAULT_SPINS,
+            #[cfg(feature = "latency")]
+            latency: None,
+            #[cfg(feature = "std")]
+            seen,
+        }
+    }
+
+    /// Loads the latest value if it is newer than the last one this reader
+    /// saw, marking it as seen and returning it along with how many values
+    /// were published since.
+    #[inline]
+    fn take_new(&mut self) -> Option<(Guard<Arc<M>>, u64)> {
+        let shared = self.upgrade()?;
+        #[cfg(feature = "latency")]
+        let (msg, version, published_at) =
+            shared.load_versioned_with(|shared| shared.published_at.load(Ordering::Acquire));
+        #[cfg(not(feature = "latency"))]
+        let (msg, version) = shared.load_versioned();
+        if !version_newer(version, self.last_seen) {
+            return None;
+        }
+        let published = versions_since(version, self.last_seen);
+        self.last_seen = version;
+        #[cfg(feature = "std")]
+        match &self.seen {
+            Some(seen) => seen.store(version, Ordering::Relaxed),
+            None => self.seen = shared.track(version),
+        }
+        #[cfg(feature = "latency")]
+        {
+            self.latency = Some(shared.age(published_at));
+        }
+        Some((msg, published))
+    }
+
+    /// Returns how long the last value `get_if_changed`, `poll_coalesced` or
+    /// `wait_new` returned had been published for when it was read, or
+    /// `None` if none has returned anything yet.
+    ///
+    /// This is the time the value spent in the `Latest`, stamped by the
+    /// writer as it swaps the value in, so it adds one clock read to every
+    /// publish and to each of those reads.
+    #[cfg(feature = "latency")]
+    #[inline]
+    pub fn last_latency(&self) -> Option<Duration> {
+        self.latency
+    }
+
+    /// Sets how many times `wait_new` and `wait_new_timeout` check for a new
+    /// value in a spin loop before going to sleep.
+    ///
+    /// Spinning wakes a reader faster when values come in quick succession,
+    /// at the cost of burning CPU while it lasts. 0 always sleeps straight
+    /// away. The default is 100, and clones keep whatever this was set to.
+    #[cfg(feature = "std")]
+    pub fn with_spins(mut self, spins: u32) -> Self {
+        self.spins = spins;
+        self
+    }
+
+    /// Upgrades to the shared state, unless the `Latest` has been dropped.
+    ///
+    /// A reader blocked in `wait_new` keeps the shared state alive past the
+    /// writer, so `closed` is checked as well.
+    #[inline]
+    fn upgrade(&self) -> Option<Arc<Shared<M>>> {
+        self.shared
+            .upgrade()
+            .filter(|shared| !shared.closed.load(Ordering::Acquire))
+    }
+
+    /// Returns the latest value without cloning the payload.
+    #[must_use]
+    #[inline]
+    pub fn get_arc(&self) -> Option<Arc<M>> {
+        self.upgrade().map(|shared| shared.latest.load_full())
+    }
+
+    /// Like `peek`, but without cloning the payload.
+    #[must_use]
+    #[inline]
+    pub fn peek_arc(&self) -> Option<Arc<M>> {
+        self.get_arc()
+    }
+
+    /// Like `get_versioned`, but without cloning the payload.
+    #[must_use]
+    #[inline]
+    pub fn get_versioned_arc(&self) -> Option<(Arc<M>, u64)> {
+        self.upgrade().map(|shared| {
+            let (value, version) = shared.load_versioned();
+            (Arc::clone(&value), version)
+        })
+    }
+
+    /// Borrows the latest value without cloning it.
+    ///
+    /// The value stays alive for as long as the guard does, even if newer
+    /// values are published in the meantime. Holding guards for long keeps
+    /// their ring slots pinned, just like holding the `Arc` from `get_arc`.
+    #[must_use]
+    #[inline]
+    pub fn guard(&self) -> Option<LatestGuard<M>> {
+        self.upgrade().map(|shared| LatestGuard {
+            guard: shared.latest.load(),
+        })
+    }
+
+    /// Borrows the part of the latest value `f` picks out, such as one field,
+    /// without cloning or copying any of it.
+    ///
+    /// The guard keeps the whole value's `Arc` alive, so like the `Arc` from
+    /// `get_arc` it pins a ring slot for as long as it is held, and it can be
+    /// sent to or shared with other threads.
+    #[must_use]
+    #[inline]
+    pub fn map_guard<T, F>(&self, f: F) -> Option<MappedGuard<M, T>>
+    where
+        T: ?Sized,
+        F: FnOnce(&M) -> &T,
+    {
+        let value = self.get_arc()?;
+        let part = NonNull::from(f(&value));
+        Some(MappedGuard {
+            _value: value,
+            part,
+        })
+    }
+
+    /// Turns this reader into one that only hands back `f` applied to the
+    /// latest value.
+    ///
+    /// `f` runs against the value in place, so only its output is cloned out
+    /// rather than the whole `M`.
+    pub fn map<T, F>(self, f: F) -> MappedReader<M, T, F>
+    where
+        F: Fn(&M) -> T,
+    {
+        MappedReader {
+            reader: self,
+            f,
+            _output: PhantomData,
+        }
+    }
+
+    /// Returns the number of values published so far, or `None` if the
+    /// `Latest` has been dropped.
+    #[must_use]
+    #[inline]
+    pub fn version(&self) -> Option<u64> {
+        self.upgrade().map(|shared| shared.version())
+    }
+
+    /// Returns how many values were published and overwritten since the last
+    /// one this reader saw through `get_if_changed` or `wait_new`.
+    ///
+    /// The latest value is not counted, since it can still be read. Returns 0
+    /// if the `Latest` has been dropped.
+    #[must_use]
+    #[inline]
+    pub fn missed_count(&self) -> u64 {
+        self.version().map_or(0, |version| {
+            versions_since(version, self.last_seen).saturating_sub(1)
+        })
+    }
+
+    /// Returns `true` once the `Latest` has been dropped.
+    ///
+    /// A reader blocked in `wait_new` keeps the shared state alive, so this
+    /// checks the `closed` flag rather than just `Weak::strong_count`.
+    #[inline]
+    pub fn is_closed(&self) -> bool {
+        self.upgrade().is_none()
+    }
+
+    /// Returns the reason the `Latest` was poisoned with, or `None` if it
+    /// hasn't been or has been dropped.
+    #[cfg(feature = "std")]
+    pub fn poison_reason(&self) -> Option<String> {
+        self.upgrade()?.poison.get().cloned()
+    }
+
+    /// Clones this reader, unless the `Latest` has been dropped and the clone
+    /// could only ever return `None`.
+    #[inline]
+    pub fn try_clone(&self) -> Option<Self> {
+        self.upgrade().map(|_| self.clone())
+    }
+
+    /// Blocks until the `Latest` is dropped, for a thread that only cares
+    /// about shutdown.
+    ///
+    /// The waiting thread counts as asleep, so while it waits every `set`
+    /// takes the lock to wake it, and it goes straight back to sleep.
+    #[cfg(feature = "std")]
+    pub fn wait_closed(&self) {
+        if let Some(shared) = self.upgrade() {
+            shared.wait_closed();
+        }
+    }
+
+    /// Turns this reader into a `Stream` of each new value as it is published.
+    ///
+    /// Like `get_if_changed`, values published while the task was not polling
+    /// are coalesced into the newest one. The stream ends once the `Latest` is
+    /// dropped.
+    #[cfg(feature = "async")]
+    pub fn into_stream(self) -> LatestStream<M> {
+        LatestStream { reader: self }
+    }
+}
+
+impl<M> LatestReader<M>
+where
+    M: Clone,
+{
+    /// Returns the latest value, whether or not this reader has seen it.
+    ///
+    /// Like every `&self` read, this leaves the version `get_if_changed`
+    /// compares against alone.
+    #[must_use]
+    #[inline]
+    pub fn get(&self) -> Option<M> {
+        // Cloning through the `load` guard skips the refcount round trip
+        // that `get_arc` pays.
+        self.upgrade()
+            .map(|shared| (**shared.latest.load()).clone())
+    }
+
+    /// Returns the latest value along with its version, as `version` would
+    /// have returned right when it was published.
+    ///
+    /// Both come from one load, so the version always belongs to the value,
+    /// unlike calling `get` and `version` one after the other. Leaves the
+    /// version `get_if_changed` compares against alone.
+    #[must_use]
+    #[inline]
+    pub fn get_versioned(&self) -> Option<(M, u64)> {
+        self.upgrade().map(|shared| {
+            let (value, version) = shared.load_versioned();
+            ((**value).clone(), version)
+        })
+    }
+
+    /// Clones the latest value into `dst` rather than into a new `M`,
+    /// returning `false` and leaving `dst` alone if the `Latest` has been
+    /// dropped.
+    ///
+    /// This goes through `Clone::clone_from`, so a payload holding a `Vec` or
+    /// `String` reuses `dst`'s buffers instead of allocating on every read.
+    #[must_use = "`dst` is left alone if the `Latest` has been dropped"]
+    #[inline]
+    pub fn get_into(&self, dst: &mut M) -> bool {
+        match self.upgrade() {
+            Some(shared) => {
+                dst.clone_from(&**shared.latest.load());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Like `get`, but says why there is no value, and fails once the
+    /// `Latest` has been poisoned.
+    ///
+    /// A poisoned `Latest` that is then dropped reports `Closed`, since the
+    /// reason goes with it.
+    #[inline]
+    pub fn try_get(&self) -> Result<M, ReadError> {
+        let shared = self.upgrade().ok_or(ReadError::Closed)?;
+        #[cfg(feature = "std")]
+        if shared.poison.get().is_some() {
+            return Err(ReadError::Poisoned);
+        }
+        let msg = (**shared.latest.load()).clone();
+        Ok(msg)
+    }
+
+    /// Returns the latest value, or `M::default()` if the `Latest` has been
+    /// dropped.
+    #[must_use]
+    #[inline]
+    pub fn get_or_default(&self) -> M
+    where
+        M: Default,
+    {
+        self.get().unwrap_or_default()
+    }
+
+    /// Returns the latest value without marking it as seen, so a following
+    /// `get_if_changed` still reports it if it is new.
+    ///
+    /// This is the same read as `get`, named for call sites that mix it with
+    /// `get_if_changed` or `wait_new`, which are the only reads that mark a
+    /// value as seen.
+    #[must_use]
+    #[inline]
+    pub fn peek(&self) -> Option<M> {
+        self.get()
+    }
+
+    /// Returns the latest value only if it was published after the last one
+    /// this reader saw through `get_if_changed` or `wait_new`, and marks it
+    /// as seen.
+    ///
+    /// Returns `None` if nothing new has been published or if the `Latest`
+    /// has been dropped.
+    #[must_use = "the value is marked as seen even if it is dropped"]
+    #[inline]
+    pub fn get_if_changed(&mut self) -> Option<M> {
+        self.take_new().map(|(msg, _)| (**msg).clone())
+    }
+
+    /// Like `get_if_changed`, but also returns how many values were published
+    /// since the last one this reader saw, counting the one returned.
+    ///
+    /// This is for a reader polling slower than the writer publishes, like a
+    /// UI redrawing each frame, that wants to know how much it coalesced. The
+    /// count comes from the same load as the value, so unlike calling
+    /// `missed_count` first it can't be off by a publish in between.
+    #[must_use = "the value is marked as seen even if it is dropped"]
+    #[inline]
+    pub fn poll_coalesced(&mut self) -> Option<(M, u64)> {
+        self.take_new()
+            .map(|(msg, published)| ((**msg).clone(), published))
+    }
+
+    /// Returns an iterator that yields whatever `get_if_changed` does until it
+    /// returns `None`.
+    ///
+    /// Only the newest value is kept, so this usually yields one value or
+    /// none. A writer publishing faster than the loop body runs can keep it
+    /// going for as long as it keeps up.
+    #[inline]
+    pub fn iter_new(&mut self) -> IterNew<'_, M> {
+        IterNew { reader: self }
+    }
+
+    /// Returns an owned copy of the latest value to serialize, or `None` if
+    /// the `Latest` has been dropped.
+    ///
+    /// This is `get`, only restricted to serializable messages so that dumping
+    /// state reads as such at the call site.
+    #[cfg(feature = "serde")]
+    #[inline]
+    pub fn snapshot_serializable(&self) -> Option<M>
+    where
+        M: serde::Serialize,
+    {
+        self.get()
+    }
+
+    /// Blocks until a value newer than the last one this reader saw through
+    /// `get_if_changed` or `wait_new` is published, then returns it.
+    ///
+    /// Returns `None` if the `Latest` is dropped, waking the reader if it was
+    /// blocked.
+    #[cfg(feature = "std")]
+    #[must_use = "the value is marked as seen even if it is dropped"]
+    pub fn wait_new(&mut self) -> Option<M> {
+        self.upgrade()?.wait_past(self.last_seen, self.spins, None);
+        self.get_if_changed()
+    }
+
+    /// Like `wait_new`, but gives up once `dur` has passed without a new
+    /// value being published.
+    #[cfg(feature = "std")]
+    #[must_use = "the value is marked as seen even if it is dropped"]
+    pub fn wait_new_timeout(&mut self, dur: Duration) -> Result<M, WaitError> {
+        let deadline = Instant::now() + dur;
+        let shared = self.upgrade().ok_or(WaitError::Closed)?;
+        if !shared.wait_past(self.last_seen, self.spins, Some(deadline)) {
+            return Err(WaitError::Timeout);
+        }
+        self.get_if_changed().ok_or(WaitError::Closed)
+    }
+}
+
+/// A borrow of the latest value, as returned by `LatestReader::guard`.
+#[derive(Debug)]
+pub struct LatestGuard<M> {
+    guard: Guard<Arc<M>>,
+}
+
+impl<M> Deref for LatestGuard<M> {
+    type Target = M;
+
+    #[inline]
+    fn deref(&self) -> &M {
+        &self.guard
+    }
+}
+
+/// A borrow of part of the latest value, as returned by
+/// `LatestReader::map_guard`.
+pub struct MappedGuard<M, T: ?Sized> {
+    /// Keeps the value `part` points into alive. `Arc`s never move what they
+    /// point to, so `part` stays put however the guard moves.
+    _value: Arc<M>,
+    part: NonNull<T>,
+}
+
+// SAFETY: the guard hands out `&T` and may drop the last `Arc<M>` on whichever
+// thread it ends up on, which is what `T: Sync` and `M: Send + Sync` allow.
+unsafe impl<M: Send + Sync, T: ?Sized + Sync> Send for MappedGuard<M, T> {}
+// SAFETY: a shared guard only ever hands out `&T`.
+unsafe impl<M: Send + Sync, T: ?Sized + Sync> Sync for MappedGuard<M, T> {}
+
+impl<M, T: ?Sized> Deref for MappedGuard<M, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        // SAFETY: `part` was a shared borrow of the value `_value` keeps
+        // alive, and an `Arc` never hands out a unique one while shared.
+        unsafe { self.part.as_ref() }
+    }
+}
+
+impl<M, T: ?Sized + fmt::Debug> fmt::Debug for MappedGuard<M, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("MappedGuard").field(&&**self).finish()
+    }
+}
+
+/// A `LatestReader` that projects each value through a function, as returned
+/// by `LatestReader::map`.
+#[derive(Debug, Clone)]
+pub struct MappedReader<M, T, F> {
+    reader: LatestReader<M>,
+    f: F,
+    _output: PhantomData<fn() -> T>,
+}
+
+impl<M, T, F> MappedReader<M, T, F>
+where
+    F: Fn(&M) -> T,
+{
+    #[must_use]
+    #[inline]
+    pub fn get(&self) -> Option<T> {
+        self.reader
+            .upgrade()
+            .map(|shared| (self.f)(&shared.latest.load()))
+    }
+}
+
+/// An iterator over the values a `LatestReader` has not seen yet, as returned
+/// by `LatestReader::iter_new`.
+#[derive(Debug)]
+pub struct IterNew<'a, M> {
+    reader: &'a mut LatestReader<M>,
+}
+
+impl<M> Iterator for IterNew<'_, M>
+where
+    M: Clone,
+{
+    type Item = M;
+
+    #[inline]
+    fn next(&mut self) -> Option<M> {
+        self.reader.get_if_changed()
+    }
+}
+
+/// The error returned by `LatestReader::try_get`.
+///
+/// More variants may be added as readers learn to fail in new ways.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ReadError {
+    /// The `Latest` was dropped.
+    Closed,
+    /// The writer gave up and called `Latest::poison`.
+    #[cfg(feature = "std")]
+    Poisoned,
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReadError::Closed => f.write_str("the Latest was dropped"),
+            #[cfg(feature = "std")]
+            ReadError::Poisoned => f.write_str("the Latest was poisoned"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for ReadError {}
+
+/// The error returned by `LatestReader::wait_new_timeout`.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaitError {
+    /// No new value was published before the timeout.
+    Timeout,
+    /// The `Latest` was dropped.
+    Closed,
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for WaitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WaitError::Timeout => f.write_str("timed out waiting for a new value"),
+            WaitError::Closed => f.write_str("the Latest was dropped"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for WaitError {}
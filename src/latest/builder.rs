@@ -0,0 +1,110 @@
+use super::{Latest, OverflowPolicy};
+
+/// Collects every option a `Latest` can be made with, for when `new`,
+/// `growable` and the `with_*` methods don't add up to one call.
+///
+/// Starts out as `Latest::new(2)` would, or with a clone of `init` in every
+/// slot if made with `LatestBuilder::new`.
+#[derive(Debug, Clone)]
+pub struct LatestBuilder<M> {
+    cap: usize,
+    max_cap: Option<usize>,
+    pow2: bool,
+    overflow: OverflowPolicy,
+    #[cfg(feature = "metrics")]
+    metrics: bool,
+    initial: M,
+}
+
+impl<M> LatestBuilder<M> {
+    /// Starts a builder whose slots all hold clones of `init`, for message
+    /// types without a sensible `Default`.
+    pub fn new(init: M) -> Self {
+        Self {
+            cap: 2,
+            max_cap: None,
+            pow2: false,
+            overflow: OverflowPolicy::Overwrite,
+            #[cfg(feature = "metrics")]
+            metrics: true,
+            initial: init,
+        }
+    }
+
+    /// Sets how many ring slots the `Latest` starts with.
+    pub fn capacity(mut self, cap: usize) -> Self {
+        self.cap = cap;
+        self
+    }
+
+    /// Lets the ring grow up to `max_cap` slots, as `Latest::growable` does.
+    pub fn growable(mut self, max_cap: usize) -> Self {
+        self.max_cap = Some(max_cap);
+        self
+    }
+
+    /// Rounds the capacity, and the maximum capacity of a growable ring, up
+    /// to a power of two, as `Latest::with_capacity_pow2` does.
+    pub fn round_up_to_pow2(mut self) -> Self {
+        self.pow2 = true;
+        self
+    }
+
+    /// Sets the `OverflowPolicy`, as `Latest::with_overflow` does.
+    pub fn overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+        self.overflow = policy;
+        self
+    }
+
+    /// Sets whether the `Latest` keeps `retry_count` and `slot_versions` up
+    /// to date. On by default, and only there at all with the `metrics`
+    /// feature.
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics(mut self, on: bool) -> Self {
+        self.metrics = on;
+        self
+    }
+
+    /// Sets the value every slot starts out holding.
+    pub fn initial(mut self, init: M) -> Self {
+        self.initial = init;
+        self
+    }
+}
+
+impl<M> LatestBuilder<M>
+where
+    M: Clone,
+{
+    /// Makes the `Latest`.
+    ///
+    /// # Panics
+    ///
+    /// Panics like `Latest::growable` if the capacity is less than 2 or the
+    /// maximum capacity is less than it.
+    pub fn build(self) -> Latest<M> {
+        let round = |cap: usize| {
+            if self.pow2 {
+                cap.next_power_of_two()
+            } else {
+                cap
+            }
+        };
+        let cap = round(self.cap);
+        let max_cap = round(self.max_cap.unwrap_or(self.cap));
+        assert!(
+            max_cap >= cap,
+            "a Latest cannot grow to {} slots from {}",
+            max_cap,
+            cap
+        );
+        let mut latest = Latest::with_initial(cap, self.initial);
+        latest.max_cap = max_cap;
+        latest.overflow = self.overflow;
+        #[cfg(feature = "metrics")]
+        {
+            latest.metrics = self.metrics;
+        }
+        latest
+    }
+}
@@ -0,0 +1,144 @@
+use super::{version_newer, Latest};
+use crate::sync::{Arc, ArcSwapOption, AtomicBool, AtomicU64, Ordering};
+use core::cmp::Ordering as CmpOrdering;
+
+/// One ring slot as seen by history readers.
+///
+/// `value` points at the same `Arc` as the writer's slot while that slot
+/// holds a published value, and is empty while the writer reuses it.
+#[derive(Debug)]
+struct Slot<M> {
+    value: ArcSwapOption<M>,
+    version: AtomicU64,
+}
+
+/// The writer's ring, mirrored for `HistoryReader`s.
+///
+/// Holding a slot's `Arc` here would pin it forever, so the writer empties a
+/// slot's entry before trying to reuse it and fills it back in afterwards.
+/// A history reader that loaded the entry in between keeps the slot pinned
+/// only for as long as its `snapshot` takes, which the writer treats like any
+/// other reader.
+#[derive(Debug)]
+pub(super) struct History<M> {
+    slots: Vec<Slot<M>>,
+    closed: AtomicBool,
+}
+
+/// Slots past the end of `slots`, which only a `Latest::resize` past the
+/// ring's maximum can add, are left out of the history.
+impl<M> History<M> {
+    /// Empties the entry for slot `idx`, returning whether it held a value.
+    pub(super) fn forget(&self, idx: usize) -> bool {
+        self.slots
+            .get(idx)
+            .is_some_and(|slot| slot.value.swap(None).is_some())
+    }
+
+    /// Puts the entry for slot `idx` back after the writer could not reuse
+    /// it, leaving its version as it was.
+    pub(super) fn restore(&self, idx: usize, value: &Arc<M>) {
+        if let Some(slot) = self.slots.get(idx) {
+            slot.value.store(Some(value.clone()));
+        }
+    }
+
+    pub(super) fn record(&self, idx: usize, value: &Arc<M>, version: u64) {
+        let slot = match self.slots.get(idx) {
+            Some(slot) => slot,
+            None => return,
+        };
+        // Stored before the value, so a reader that loads the value sees the
+        // version it was published with.
+        slot.version.store(version, Ordering::Release);
+        slot.value.store(Some(value.clone()));
+    }
+
+    pub(super) fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+    }
+}
+
+impl<M> Latest<M>
+where
+    M: Clone,
+{
+    /// Creates a `HistoryReader` over this `Latest`.
+    ///
+    /// The writer only starts keeping history when the first one is created,
+    /// so the history starts out with just the current value.
+    pub fn history_reader(&self) -> HistoryReader<M> {
+        let history = self.history.get_or_init(|| {
+            // Sized for the largest the ring can grow to, so that slots added
+            // by a `growable` one have somewhere to be recorded.
+            let slots: Vec<_> = (0..self.max_cap)
+                .map(|_| Slot {
+                    value: ArcSwapOption::empty(),
+                    version: AtomicU64::new(0),
+                })
+                .collect();
+            let history = History {
+                slots,
+                closed: AtomicBool::new(false),
+            };
+            let version = self.shared.version();
+            history.record(self.index, &self.q[self.index], version);
+            Arc::new(history)
+        });
+        HistoryReader {
+            history: history.clone(),
+        }
+    }
+}
+
+/// A reader that sees the last few values published to a `Latest`, as
+/// returned by `Latest::history_reader`.
+///
+/// The window is made of whatever is still sitting in the writer's ring, so
+/// it holds at most as many values as the ring has slots, and one fewer while
+/// a `set` is underway.
+#[derive(Debug, Clone)]
+pub struct HistoryReader<M> {
+    history: Arc<History<M>>,
+}
+
+impl<M> HistoryReader<M>
+where
+    M: Clone,
+{
+    /// Returns the values still in the ring, oldest first.
+    ///
+    /// Values are ordered by version but are not necessarily consecutive. The
+    /// writer may lap the reader while the snapshot is taken, or reuse a
+    /// slot as soon as no reader holds it, so entries can be missing from
+    /// the middle of the window. Returns an empty `Vec` once the `Latest` has
+    /// been dropped.
+    #[must_use]
+    pub fn snapshot(&self) -> Vec<M> {
+        if self.history.closed.load(Ordering::Acquire) {
+            return Vec::new();
+        }
+        let mut values: Vec<_> = self
+            .history
+            .slots
+            .iter()
+            .filter_map(|slot| {
+                let value = slot.value.load_full()?;
+                Some((slot.version.load(Ordering::Acquire), value))
+            })
+            .collect();
+        values.sort_by(|&(a, _), &(b, _)| {
+            if a == b {
+                CmpOrdering::Equal
+            } else if version_newer(a, b) {
+                CmpOrdering::Greater
+            } else {
+                CmpOrdering::Less
+            }
+        });
+        values
+            .into_iter()
+            .map(|(_, value)| (*value).clone())
+            .collect()
+    }
+}
@@ -0,0 +1,73 @@
+use super::{Latest, LatestReader};
+use core::ops::Deref;
+use std::time::{Duration, Instant};
+
+/// A `Latest` that publishes at most once per `interval`, dropping whatever
+/// is set in between.
+///
+/// This throttles a producer that fires faster than anyone downstream can
+/// use, so each version stands for a real publish rather than for however
+/// many values readers would have coalesced. It derefs to the `Latest` for
+/// everything but publishing.
+#[derive(Debug)]
+pub struct RateLimited<M> {
+    latest: Latest<M>,
+    interval: Duration,
+    last: Option<Instant>,
+}
+
+impl<M> RateLimited<M> {
+    /// Wraps `latest`, letting the first `set` through straight away.
+    pub fn new(latest: Latest<M>, interval: Duration) -> Self {
+        Self {
+            latest,
+            interval,
+            last: None,
+        }
+    }
+
+    /// Publishes `msg` if at least `interval` has passed since the last value
+    /// was, and drops it otherwise, returning whether it was published.
+    ///
+    /// A value the `OverflowPolicy` drops doesn't count as published, so the
+    /// next one can go straight through.
+    ///
+    /// # Panics
+    ///
+    /// Panics like `Latest::set` if every ring slot is held by a reader,
+    /// unless the `OverflowPolicy` is `Drop`.
+    pub fn set(&mut self, msg: M) -> bool {
+        let now = Instant::now();
+        if self
+            .last
+            .is_some_and(|last| now.duration_since(last) < self.interval)
+        {
+            return false;
+        }
+        if !self.latest.set_published(msg) {
+            return false;
+        }
+        self.last = Some(now);
+        true
+    }
+
+    /// Like `Latest::reader`.
+    #[inline]
+    pub fn reader(&self) -> LatestReader<M> {
+        self.latest.reader()
+    }
+
+    /// Unwraps the `Latest`, to publish without a limit again.
+    pub fn into_inner(self) -> Latest<M> {
+        self.latest
+    }
+}
+
+impl<M> Deref for RateLimited<M> {
+    type Target = Latest<M>;
+
+    #[inline]
+    fn deref(&self) -> &Latest<M> {
+        &self.latest
+    }
+}
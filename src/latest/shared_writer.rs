@@ -0,0 +1,169 @@
+use super::{CasError, LatestReader, SetError, Shared};
+use crate::sync::{self, Arc, AtomicUsize, Mutex, Ordering, TryLockError};
+
+/// The ring behind a `SharedWriter`, shared by all of its clones.
+///
+/// Unlike `Latest`, several writers can be looking for a slot at once, so a
+/// writer has to claim a slot before checking whether any reader still holds
+/// it. Claiming is a `try_lock`, so a writer that finds a slot claimed by
+/// another one just moves on to the next.
+#[derive(Debug)]
+struct Ring<M> {
+    q: Vec<Mutex<Arc<M>>>,
+    shared: Arc<Shared<M>>,
+    index: AtomicUsize,
+}
+
+impl<M> Drop for Ring<M> {
+    fn drop(&mut self) {
+        self.shared.closed.store(true, Ordering::SeqCst);
+        self.shared.wake();
+    }
+}
+
+/// A cloneable writer for a `Latest` that several threads publish to.
+///
+/// When two writers race, whichever stores into the shared cell last wins and
+/// is what readers see, which is not necessarily the one that started
+/// publishing last. Versions are still bumped once per publish, but while two
+/// publishes overlap a version may be paired with the other writer's value,
+/// even by `LatestReader::get_versioned`. Readers see the
+/// `Latest` as closed once every clone of the writer is dropped.
+#[derive(Debug)]
+pub struct SharedWriter<M> {
+    ring: Arc<Ring<M>>,
+}
+
+impl<M> Clone for SharedWriter<M> {
+    fn clone(&self) -> Self {
+        Self {
+            ring: self.ring.clone(),
+        }
+    }
+}
+
+impl<M> SharedWriter<M>
+where
+    M: Default + Clone,
+{
+    /// Creates a `SharedWriter` with `cap` ring slots, all holding
+    /// `M::default()`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cap` is less than 2.
+    pub fn new(cap: usize) -> Self {
+        Self::with_initial(cap, M::default())
+    }
+}
+
+impl<M> SharedWriter<M>
+where
+    M: Clone,
+{
+    /// Creates a `SharedWriter` with `cap` ring slots, all holding a clone of
+    /// `init`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cap` is less than 2.
+    pub fn with_initial(cap: usize, init: M) -> Self {
+        assert!(cap >= 2, "a Latest needs at least 2 slots, got {}", cap);
+        let mut q = Vec::with_capacity(cap);
+        for _ in 1..cap {
+            q.push(Mutex::new(Arc::new(init.clone())));
+        }
+        q.push(Mutex::new(Arc::new(init)));
+
+        let shared = Arc::new(Shared::new(q[0].lock().unwrap().clone()));
+
+        Self {
+            ring: Arc::new(Ring {
+                q,
+                shared,
+                index: AtomicUsize::new(0),
+            }),
+        }
+    }
+}
+
+impl<M> SharedWriter<M> {
+    #[inline]
+    pub fn reader(&self) -> LatestReader<M> {
+        LatestReader::new(sync::downgrade(&self.ring.shared), 0)
+    }
+
+    /// Creates `n` independent readers at once, each as if from `reader`.
+    pub fn readers(&self, n: usize) -> Vec<LatestReader<M>> {
+        (0..n).map(|_| self.reader()).collect()
+    }
+
+    /// Publishes `msg` into the next ring slot that neither a reader nor
+    /// another writer is holding.
+    ///
+    /// Like `Latest::try_set`, this gives up after sweeping the ring once.
+    pub fn try_set(&self, msg: M) -> Result<(), SetError<M>> {
+        self.try_publish(msg, |shared, published| {
+            shared.publish(published);
+        })
+        .map_err(SetError)
+    }
+
+    /// Like `Latest::compare_and_set`, but the version check and the publish
+    /// are one compare-and-swap, so of several writers expecting the same
+    /// version exactly one succeeds.
+    ///
+    /// A writer that loses the race has still claimed and written a slot by
+    /// then, which just goes unpublished.
+    ///
+    /// # Panics
+    ///
+    /// Panics like `set` if every ring slot is held by a reader or another
+    /// writer.
+    pub fn compare_and_set(&self, expected_version: u64, msg: M) -> Result<u64, CasError> {
+        let actual = self.ring.shared.version();
+        if actual != expected_version {
+            return Err(CasError::Moved(actual));
+        }
+        match self.try_publish(msg, |shared, published| {
+            shared.publish_if(expected_version, published)
+        }) {
+            Ok(result) => result.map_err(CasError::Moved),
+            Err(_) => panic!("every slot in the ring is held by a reader"),
+        }
+    }
+
+    /// Writes `msg` into the next ring slot that neither a reader nor another
+    /// writer is holding and hands it to `publish`, returning what that did,
+    /// or `msg` back if every slot was held after one sweep.
+    fn try_publish<R>(
+        &self,
+        msg: M,
+        publish: impl FnOnce(&Shared<M>, Arc<M>) -> R,
+    ) -> Result<R, M> {
+        let q = &self.ring.q;
+        for _ in 0..q.len() {
+            let idx = self.ring.index.fetch_add(1, Ordering::Relaxed) % q.len();
+            let mut slot = match q[idx].try_lock() {
+                Ok(slot) => slot,
+                Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+                Err(TryLockError::WouldBlock) => continue,
+            };
+            if let Some(value) = Arc::get_mut(&mut slot) {
+                *value = msg;
+                let published = slot.clone();
+                drop(slot);
+                return Ok(publish(&self.ring.shared, published));
+            }
+        }
+        Err(msg)
+    }
+
+    /// Like `try_set`, but panics if every ring slot is held by a reader or
+    /// another writer.
+    pub fn set(&self, msg: M) {
+        if self.try_set(msg).is_err() {
+            panic!("every slot in the ring is held by a reader");
+        }
+    }
+}
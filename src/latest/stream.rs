@@ -0,0 +1,76 @@
+use super::{LatestReader, Shared};
+use crate::sync::Ordering;
+use futures_core::Stream;
+use std::future;
+use std::pin::Pin;
+use std::task::{Context, Poll, Waker};
+
+impl<M> Shared<M> {
+    /// Parks `waker` until the next publish or the writer being dropped.
+    ///
+    /// A task polled again before anything is published usually hands in the
+    /// same waker, so that one is not stored twice. Wakers of streams dropped
+    /// while parked linger until the next publish clears them all.
+    fn register(&self, waker: &Waker) {
+        let mut wakers = self.wakers.lock().unwrap();
+        if wakers.iter().any(|parked| parked.will_wake(waker)) {
+            return;
+        }
+        wakers.push(waker.clone());
+        self.waiters.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+impl<M> LatestReader<M>
+where
+    M: Clone,
+{
+    /// Resolves once a value newer than the last one this reader saw is
+    /// published, then returns it.
+    ///
+    /// Any number of publishes before the task gets polled again wake it once
+    /// and return only the newest. Returns `None` if the `Latest` is dropped.
+    pub async fn get_new(&mut self) -> Option<M> {
+        future::poll_fn(|cx| self.poll_new(cx)).await
+    }
+
+    fn poll_new(&mut self, cx: &mut Context) -> Poll<Option<M>> {
+        let shared = match self.upgrade() {
+            Some(shared) => shared,
+            None => return Poll::Ready(None),
+        };
+        if let Some(msg) = self.get_if_changed() {
+            return Poll::Ready(Some(msg));
+        }
+
+        shared.register(cx.waker());
+        // The writer may have published between the check above and parking
+        // the waker, in which case it saw no waiters and woke nobody. Both
+        // sides go through `SeqCst`, so checking again catches that.
+        if shared.version() != self.last_seen {
+            return Poll::Ready(self.get_if_changed());
+        }
+        if shared.closed.load(Ordering::SeqCst) {
+            return Poll::Ready(None);
+        }
+        Poll::Pending
+    }
+}
+
+/// A `Stream` of each new value published to a `Latest`, as returned by
+/// `LatestReader::into_stream`.
+#[derive(Debug, Clone)]
+pub struct LatestStream<M> {
+    pub(super) reader: LatestReader<M>,
+}
+
+impl<M> Stream for LatestStream<M>
+where
+    M: Clone,
+{
+    type Item = M;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<M>> {
+        self.get_mut().reader.poll_new(cx)
+    }
+}
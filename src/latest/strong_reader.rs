@@ -0,0 +1,73 @@
+use super::{Latest, Shared};
+use crate::sync::{Arc, Ordering};
+
+impl<M> Latest<M> {
+    /// Creates a reader that keeps the shared cell alive, so it can still
+    /// read the final value after the `Latest` is dropped.
+    ///
+    /// See `StrongReader` for what that costs.
+    #[inline]
+    pub fn strong_reader(&self) -> StrongReader<M> {
+        StrongReader {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+/// A reader of a `Latest` that owns a strong reference to the shared cell
+/// rather than a `Weak`, to serve the last known value once the writer is
+/// gone.
+///
+/// Dropping the `Latest` frees its ring but not the cell, so the final value
+/// and the cell's wait state stay allocated until the last `StrongReader` is
+/// dropped too. Only the value in the cell is kept, not the ring. Strong
+/// readers are not counted by `Latest::reader_count`, and `is_closed` still
+/// reports the writer going away.
+#[derive(Debug)]
+pub struct StrongReader<M> {
+    shared: Arc<Shared<M>>,
+}
+
+impl<M> Clone for StrongReader<M> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<M> StrongReader<M> {
+    /// Returns the latest value without cloning the payload. Once the
+    /// `Latest` is dropped this is the last value it published.
+    #[must_use]
+    #[inline]
+    pub fn get_arc(&self) -> Arc<M> {
+        self.shared.latest.load_full()
+    }
+
+    /// Returns the number of values published so far.
+    #[must_use]
+    #[inline]
+    pub fn version(&self) -> u64 {
+        self.shared.version()
+    }
+
+    /// Returns `true` once the `Latest` has been dropped.
+    #[inline]
+    pub fn is_closed(&self) -> bool {
+        self.shared.closed.load(Ordering::Acquire)
+    }
+}
+
+impl<M> StrongReader<M>
+where
+    M: Clone,
+{
+    /// Returns a copy of the latest value, which once the `Latest` is
+    /// dropped is the last value it published.
+    #[must_use]
+    #[inline]
+    pub fn get(&self) -> M {
+        (**self.shared.latest.load()).clone()
+    }
+}
@@ -0,0 +1,29 @@
+use super::Latest;
+use tokio::sync::watch;
+
+impl<M> Latest<M>
+where
+    M: Clone + Send + Sync + 'static,
+{
+    /// Forwards every value published from now on into a new tokio `watch`
+    /// channel, for async code that already consumes one.
+    ///
+    /// The channel starts out holding the current value. No task is spawned,
+    /// so no runtime is needed: the `watch::Sender` lives in an `on_publish`
+    /// callback, and each `set` clones the value into it on the writer's
+    /// thread. The first publish after every receiver has been dropped drops
+    /// the callback and the sender with it. Dropping the `Latest` drops the
+    /// sender too, after which `changed` on the receivers returns an error,
+    /// while `borrow` still sees the last value.
+    pub fn watch(&mut self) -> watch::Receiver<M> {
+        let (tx, rx) = watch::channel((*self.q[self.index]).clone());
+        self.observe(move |msg| {
+            if tx.is_closed() {
+                return false;
+            }
+            tx.send_replace(msg.clone());
+            true
+        });
+        rx
+    }
+}
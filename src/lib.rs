@@ -0,0 +1,46 @@
+//! A latest-value primitive for sharing state between threads, along with
+//! benchmarks comparing it against the usual alternatives.
+//!
+//! The main types are re-exported here, so `rio_thread_bench::Latest` and
+//! `rio_thread_bench::latest::Latest` name the same thing.
+//!
+//! The `std` feature, on by default, adds the blocking `wait_new` family,
+//! `SharedWriter` and `RateLimited`. Without it the crate is `no_std` and
+//! only needs `alloc`, with `Latest` and `LatestReader` still usable for
+//! reads and writes. `get`, `get_arc` and `guard` never wait on the writer,
+//! so an interrupt handler can call them while the code it interrupted is in
+//! the middle of `set`. Reads that pair the value with its version, such as
+//! `get_if_changed` and `get_versioned`, wait for a publish in flight to
+//! finish, so on a single core one called from an interrupt that preempted
+//! `set` never returns. Read those from the same context as the writer, or
+//! mask the interrupt around `set`. The `async` feature adds
+//! `LatestReader::into_stream` and `Latest::watch`, the `serde` feature
+//! makes the payloads in `message` serializable, the `metrics` feature adds
+//! `Latest::retry_count` and `Latest::slot_versions`, the `latency` feature
+//! adds `LatestReader::last_latency`, and the `zeroize` feature adds
+//! `Latest::with_zero_on_reclaim`. `debug-invariants` checks the ring after
+//! every publish, for when changing `Latest` itself. `ffi` has a C function
+//! for reading a `Latest` of `message::Message` from outside of Rust.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod ffi;
+pub mod latest;
+pub mod message;
+pub mod seqlock;
+pub mod spsc;
+mod sync;
+
+#[cfg(feature = "async")]
+pub use crate::latest::LatestStream;
+pub use crate::latest::{
+    channel, snapshot_all, CapacityError, CasError, IterNew, Latest, LatestBuilder, LatestGuard,
+    LatestReader, LatestWriter, MappedGuard, MappedReader, OverflowPolicy, ReadError, ResizeError,
+    SetError, StrongReader,
+};
+#[cfg(feature = "std")]
+pub use crate::latest::{HistoryReader, RateLimited, SharedWriter, WaitError};
+pub use crate::seqlock::{SeqLatest, SeqReader};
+pub use crate::spsc::{SpscLatest, SpscReader};
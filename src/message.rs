@@ -0,0 +1,55 @@
+//! Payloads of a few sizes for the benchmarks to publish.
+
+/// A message type the benchmarks can be run over.
+pub trait Payload: Default + Copy + Send + Sync + 'static {
+    fn new(x: f64, y: f64) -> Self;
+    fn y(&self) -> f64;
+}
+
+/// A payload of `16 + N` bytes, to model real messages of a given size.
+///
+/// Left as just `Message`, it is 96 bytes. Defaults only apply to types, so
+/// where nothing else pins down `N`, call its functions as `<Message>::new`.
+///
+/// It is `repr(C)`, so that `ffi` can hand values to C.
+// Only `y` is ever read; `dummy` pads out the payload.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[repr(C)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Message<const N: usize = 80> {
+    pub x: f64,
+    pub y: f64,
+    // serde only implements its traits for arrays of up to 32.
+    #[cfg_attr(feature = "serde", serde(with = "serde_big_array::BigArray"))]
+    pub dummy: [u8; N],
+}
+
+/// A 16 byte payload.
+pub type SmallMessage = Message<0>;
+
+/// A 1 KiB payload.
+pub type LargeMessage = Message<1008>;
+
+impl<const N: usize> Default for Message<N> {
+    fn default() -> Self {
+        Self {
+            x: 0.,
+            y: 0.,
+            dummy: [0; N],
+        }
+    }
+}
+
+impl<const N: usize> Payload for Message<N> {
+    fn new(x: f64, y: f64) -> Self {
+        Self {
+            x,
+            y,
+            dummy: [50; N],
+        }
+    }
+
+    fn y(&self) -> f64 {
+        self.y
+    }
+}
@@ -0,0 +1,122 @@
+//! A latest-value cell for `Copy` payloads that never allocates or touches a
+//! reference count.
+//!
+//! `SeqLatest` keeps two copies of the value and a sequence number, in the
+//! style of the Linux kernel's `seqcount_latch`. The writer bumps the
+//! sequence before overwriting each copy in turn, and readers read the copy
+//! the writer is not currently touching, retrying only if the sequence moved
+//! while they were reading. A read therefore always finds a complete value
+//! without waiting for a `set` to finish.
+
+use crate::sync::{fence, Arc, AtomicBool, AtomicUsize, Ordering};
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::ptr;
+
+#[derive(Debug)]
+struct Shared<M> {
+    seq: AtomicUsize,
+    copies: [UnsafeCell<M>; 2],
+    closed: AtomicBool,
+}
+
+// SAFETY: the copies are only written by the one `SeqLatest` and only read
+// under the sequence check, which throws away any read that raced a write.
+unsafe impl<M: Copy + Send> Sync for Shared<M> {}
+
+/// The writer of a seqlock-based latest value. See the module docs.
+///
+/// Everything here needs `M: Copy`. A read copies the value out while a
+/// `set` may be overwriting it and throws the copy away if it was torn, which
+/// is only sound for a type that a bitwise copy fully duplicates and that has
+/// nothing to drop. For `String` and other owning payloads, use `Latest`.
+#[derive(Debug)]
+pub struct SeqLatest<M> {
+    shared: Arc<Shared<M>>,
+}
+
+impl<M> SeqLatest<M>
+where
+    M: Copy,
+{
+    pub fn new(init: M) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                seq: AtomicUsize::new(0),
+                copies: [UnsafeCell::new(init), UnsafeCell::new(init)],
+                closed: AtomicBool::new(false),
+            }),
+        }
+    }
+
+    #[inline]
+    pub fn reader(&self) -> SeqReader<M> {
+        SeqReader {
+            shared: self.shared.clone(),
+        }
+    }
+
+    /// Publishes `msg`, overwriting both copies.
+    #[inline]
+    pub fn set(&mut self, msg: M) {
+        let shared = &*self.shared;
+        let seq = shared.seq.load(Ordering::Relaxed);
+        for (i, copy) in shared.copies.iter().enumerate() {
+            // An odd sequence steers readers to the second copy while the
+            // first is written, and an even one back to the first.
+            shared.seq.store(seq.wrapping_add(i + 1), Ordering::Release);
+            fence(Ordering::Release);
+            // SAFETY: only this writer writes the copies, and readers that
+            // race the write notice the sequence change and retry.
+            unsafe { ptr::write_volatile(copy.get(), msg) };
+        }
+    }
+}
+
+impl<M> Drop for SeqLatest<M> {
+    fn drop(&mut self) {
+        self.shared.closed.store(true, Ordering::Release);
+    }
+}
+
+/// A reader of a `SeqLatest`.
+#[derive(Debug)]
+pub struct SeqReader<M> {
+    shared: Arc<Shared<M>>,
+}
+
+impl<M> Clone for SeqReader<M> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<M> SeqReader<M>
+where
+    M: Copy,
+{
+    /// Returns a copy of the latest value, or `None` if the `SeqLatest` has
+    /// been dropped.
+    #[must_use]
+    #[inline]
+    pub fn get(&self) -> Option<M> {
+        let shared = &*self.shared;
+        if shared.closed.load(Ordering::Acquire) {
+            return None;
+        }
+        loop {
+            let seq = shared.seq.load(Ordering::Acquire);
+            let copy = &shared.copies[seq & 1];
+            // SAFETY: read as `MaybeUninit` since a racing write can leave a
+            // torn value that is not a valid `M`. It is only assumed to be
+            // initialized once the sequence check shows no write raced it.
+            let value = unsafe { ptr::read_volatile(copy.get() as *const MaybeUninit<M>) };
+            fence(Ordering::Acquire);
+            if shared.seq.load(Ordering::Relaxed) == seq {
+                return Some(unsafe { value.assume_init() });
+            }
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! A latest-value cell for exactly one reader, which reads without touching a
+//! reference count or copying the value.
+//!
+//! `SpscLatest` is a triple buffer. Of its three slots the writer owns one to
+//! write into and the reader one to read from, and the third sits in the
+//! middle holding the newest value the reader hasn't taken yet. Publishing
+//! swaps the writer's slot into the middle, and a read that finds a fresh
+//! value there swaps its own slot in for it. Each slot only ever belongs to
+//! one side, so neither ever waits on the other, and a read that finds
+//! nothing new is a single load.
+//!
+//! With a second reader the middle slot would have two takers, so
+//! `SpscLatest::reader` hands one out only once.
+
+use crate::sync::{Arc, AtomicBool, AtomicUsize, CachePadded, Ordering};
+use core::cell::UnsafeCell;
+
+/// Set in `middle` when the writer has left a value there that the reader
+/// hasn't taken yet.
+const FRESH: usize = 0b100;
+
+#[derive(Debug)]
+struct Shared<M> {
+    // Padded so that the writer filling its slot doesn't keep stealing the
+    // cache line of the one the reader is reading.
+    slots: [CachePadded<UnsafeCell<M>>; 3],
+    /// The index of the middle slot, along with `FRESH`.
+    middle: CachePadded<AtomicUsize>,
+    closed: AtomicBool,
+}
+
+// SAFETY: every slot belongs to exactly one of the writer, the reader and the
+// middle at a time, and only changes hands through the `AcqRel` swaps of
+// `middle`, so no slot is ever touched from two threads at once. Values do
+// move from the writer's thread to the reader's, hence `M: Send`.
+unsafe impl<M: Send> Sync for Shared<M> {}
+
+/// The writer of a single-reader latest value. See the module docs.
+#[derive(Debug)]
+pub struct SpscLatest<M> {
+    shared: Arc<Shared<M>>,
+    back: usize,
+    reader_taken: bool,
+}
+
+impl<M> SpscLatest<M>
+where
+    M: Clone,
+{
+    /// Creates an `SpscLatest` with every slot holding a clone of `init`.
+    pub fn new(init: M) -> Self {
+        Self {
+            shared: Arc::new(Shared {
+                slots: [
+                    CachePadded(UnsafeCell::new(init.clone())),
+                    CachePadded(UnsafeCell::new(init.clone())),
+                    CachePadded(UnsafeCell::new(init)),
+                ],
+                middle: CachePadded(AtomicUsize::new(1)),
+                closed: AtomicBool::new(false),
+            }),
+            back: 2,
+            reader_taken: false,
+        }
+    }
+}
+
+impl<M> SpscLatest<M> {
+    /// Returns the reader the first time it is called, and `None` after.
+    #[inline]
+    pub fn reader(&mut self) -> Option<SpscReader<M>> {
+        if self.reader_taken {
+            return None;
+        }
+        self.reader_taken = true;
+        Some(SpscReader {
+            shared: self.shared.clone(),
+            front: 0,
+        })
+    }
+
+    /// Publishes `msg`, dropping whichever older value its slot held.
+    #[inline]
+    pub fn set(&mut self, msg: M) {
+        let shared = &*self.shared;
+        // SAFETY: `back` is the writer's own slot, which the reader can't
+        // reach until the swap below hands it over.
+        unsafe { *shared.slots[self.back].get() = msg };
+        let old = shared.middle.swap(self.back | FRESH, Ordering::AcqRel);
+        self.back = old & !FRESH;
+    }
+}
+
+impl<M> Drop for SpscLatest<M> {
+    fn drop(&mut self) {
+        self.shared.closed.store(true, Ordering::Release);
+    }
+}
+
+/// The one reader of an `SpscLatest`.
+#[derive(Debug)]
+pub struct SpscReader<M> {
+    shared: Arc<Shared<M>>,
+    front: usize,
+}
+
+impl<M> SpscReader<M> {
+    /// Returns a borrow of the latest value, or `None` if the `SpscLatest`
+    /// has been dropped.
+    ///
+    /// The borrow is of the reader's own slot, so the writer can keep
+    /// publishing while it is held; the next `get` picks up where it got to.
+    #[must_use]
+    #[inline]
+    pub fn get(&mut self) -> Option<&M> {
+        let shared = &*self.shared;
+        if shared.closed.load(Ordering::Acquire) {
+            return None;
+        }
+        if shared.middle.load(Ordering::Relaxed) & FRESH != 0 {
+            let old = shared.middle.swap(self.front, Ordering::AcqRel);
+            self.front = old & !FRESH;
+        }
+        // SAFETY: `front` is the reader's own slot, which the writer can't
+        // reach until a later `get` swaps it back into the middle, and that
+        // takes `&mut self` and so ends this borrow first.
+        Some(unsafe { &*shared.slots[self.front].get() })
+    }
+}
@@ -0,0 +1,315 @@
+//! The synchronization primitives `Latest` is built on.
+//!
+//! Building with `RUSTFLAGS="--cfg loom"` swaps them for loom's models so that
+//! `tests/loom.rs` can check every interleaving. loom has neither `Weak` nor
+//! anything like `ArcSwap`, so under loom the shared cell is a mutex around an
+//! `Arc` and readers hold a strong reference, relying on the `closed` flag
+//! alone to notice the writer going away.
+//!
+//! Without the `std` feature there is no `arc_swap`, so the shared cell is a
+//! reader-counting `ArcSwap` defined below, and `AtomicU64` comes from
+//! `portable_atomic` for targets without native 64-bit atomics.
+
+#[cfg(feature = "std")]
+pub(crate) use std::sync::TryLockError;
+
+#[cfg(not(loom))]
+pub(crate) use alloc::sync::{Arc, Weak};
+
+#[cfg(not(loom))]
+#[inline]
+pub(crate) fn downgrade<T>(arc: &Arc<T>) -> Weak<T> {
+    Arc::downgrade(arc)
+}
+
+/// Counts the `Weak`s, and so the readers, pointing at `arc`.
+#[cfg(not(loom))]
+#[inline]
+pub(crate) fn weak_count<T>(arc: &Arc<T>) -> usize {
+    Arc::weak_count(arc)
+}
+
+#[cfg(all(feature = "std", not(loom)))]
+pub(crate) use arc_swap::{ArcSwap, ArcSwapOption, Guard};
+#[cfg(all(feature = "std", not(loom)))]
+pub(crate) use std::sync::atomic::{fence, AtomicBool, AtomicU64, AtomicUsize, Ordering};
+#[cfg(all(feature = "std", not(loom)))]
+pub(crate) use std::sync::{Condvar, Mutex};
+
+#[cfg(not(feature = "std"))]
+pub(crate) use core::sync::atomic::{fence, AtomicBool, AtomicUsize, Ordering};
+#[cfg(not(feature = "std"))]
+pub(crate) use portable_atomic::AtomicU64;
+
+#[cfg(loom)]
+pub(crate) use loom::sync::atomic::{fence, AtomicBool, AtomicU64, AtomicUsize, Ordering};
+#[cfg(loom)]
+pub(crate) use loom::sync::{Arc, Condvar, Mutex};
+
+// loom has to be told about spinning, or it keeps scheduling the spinner.
+#[cfg(not(loom))]
+pub(crate) use core::hint::spin_loop;
+#[cfg(loom)]
+pub(crate) use loom::hint::spin_loop;
+
+#[cfg(all(not(feature = "std"), not(loom)))]
+pub(crate) use core::hint::spin_loop as yield_now;
+#[cfg(loom)]
+pub(crate) use loom::thread::yield_now;
+/// Gives up the rest of the timeslice, so that a thread this one is waiting
+/// on gets to run even if they share a core. Without `std` there is no
+/// scheduler to yield to, so this is only a `spin_loop`.
+#[cfg(all(feature = "std", not(loom)))]
+pub(crate) use std::thread::yield_now;
+
+/// Pads and aligns `T` to its own cache line so that writes to it do not
+/// invalidate neighbouring fields, after `crossbeam_utils::CachePadded`.
+///
+/// Current x86-64 and aarch64 parts prefetch cache lines in pairs, so those
+/// get 128 bytes rather than 64.
+#[cfg_attr(any(target_arch = "x86_64", target_arch = "aarch64"), repr(align(128)))]
+#[cfg_attr(
+    not(any(target_arch = "x86_64", target_arch = "aarch64")),
+    repr(align(64))
+)]
+#[derive(Debug)]
+pub(crate) struct CachePadded<T>(pub(crate) T);
+
+impl<T> core::ops::Deref for CachePadded<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+/// Stands in for `arc_swap::Guard`, which derefs to the `Arc` it loaded.
+#[cfg(any(loom, not(feature = "std")))]
+#[derive(Debug)]
+pub(crate) struct Guard<T>(T);
+
+#[cfg(any(loom, not(feature = "std")))]
+impl<T> core::ops::Deref for Guard<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+/// How many load counters the `no_std` `ArcSwap` spreads its epochs over.
+#[cfg(all(not(feature = "std"), not(loom)))]
+const EPOCHS: usize = 8;
+
+/// Stands in for `arc_swap::ArcSwap` without the standard library.
+///
+/// The cell holds an `Arc` turned into a raw pointer. A load counts itself in
+/// the `readers` counter for the current `epoch` for as long as it takes to
+/// read the pointer and bump the `Arc`'s count, and never waits on anything,
+/// so an interrupt handler can load while the context it interrupted is
+/// halfway through a store. The load only keeps the pointer if the epoch is
+/// still the same afterwards, and tries again otherwise.
+///
+/// Every store moves the epoch on, skipping counters a load is still in, and
+/// then swaps the pointer. A load can only have read the old pointer under
+/// the epoch it was stored in or the one the store has just moved to, so the
+/// store keeps it in `retired` until both of those counters have read 0 and
+/// drops it then, on this store or a later one. A load stalled halfway, say
+/// in a preempted thread, keeps at most the one or two values it could have
+/// read and their ring slots from being reused. A writer that finds every
+/// slot held calls `reclaim` to drop what it can without storing anything.
+/// Stores take turns through `storing`, which loads never touch.
+#[cfg(all(not(feature = "std"), not(loom)))]
+#[derive(Debug)]
+pub(crate) struct ArcSwap<T> {
+    ptr: core::sync::atomic::AtomicPtr<T>,
+    epoch: AtomicUsize,
+    readers: [AtomicUsize; EPOCHS],
+    storing: AtomicBool,
+    /// Swapped out values along with the two counters that have to read 0
+    /// before they are dropped.
+    retired: core::cell::UnsafeCell<alloc::vec::Vec<(Arc<T>, usize, usize)>>,
+}
+
+#[cfg(all(not(feature = "std"), not(loom)))]
+impl<T> ArcSwap<T> {
+    pub(crate) fn new(value: Arc<T>) -> Self {
+        ArcSwap {
+            ptr: core::sync::atomic::AtomicPtr::new(Arc::into_raw(value) as *mut T),
+            epoch: AtomicUsize::new(0),
+            readers: Default::default(),
+            storing: AtomicBool::new(false),
+            retired: core::cell::UnsafeCell::new(alloc::vec::Vec::new()),
+        }
+    }
+
+    pub(crate) fn store(&self, value: Arc<T>) {
+        while self
+            .storing
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            core::hint::spin_loop();
+        }
+        let epoch = self.epoch.load(Ordering::Relaxed);
+        let mut next = epoch.wrapping_add(1);
+        for _ in 1..EPOCHS {
+            if self.readers[next % EPOCHS].load(Ordering::SeqCst) == 0 {
+                break;
+            }
+            next = next.wrapping_add(1);
+        }
+        self.epoch.store(next, Ordering::SeqCst);
+        let old = self
+            .ptr
+            .swap(Arc::into_raw(value) as *mut T, Ordering::SeqCst);
+        // SAFETY: `storing` is held, so no other store touches `retired`.
+        let retired = unsafe { &mut *self.retired.get() };
+        // SAFETY: every pointer in the cell came from `Arc::into_raw`, and
+        // swapping it out hands this store the cell's reference.
+        retired.push((unsafe { Arc::from_raw(old) }, epoch % EPOCHS, next % EPOCHS));
+        self.drop_unreachable(retired);
+        self.storing.store(false, Ordering::Release);
+    }
+
+    /// Drops whatever earlier stores retired that no load can still reach.
+    pub(crate) fn reclaim(&self) {
+        if self
+            .storing
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+        {
+            // SAFETY: `storing` is held, so no store touches `retired`.
+            self.drop_unreachable(unsafe { &mut *self.retired.get() });
+            self.storing.store(false, Ordering::Release);
+        }
+    }
+
+    /// Does the work of `reclaim` once `storing` is held.
+    fn drop_unreachable(&self, retired: &mut alloc::vec::Vec<(Arc<T>, usize, usize)>) {
+        retired.retain(|&(_, stored, swapped)| {
+            self.readers[stored].load(Ordering::SeqCst) != 0
+                || self.readers[swapped].load(Ordering::SeqCst) != 0
+        });
+    }
+
+    pub(crate) fn load(&self) -> Guard<Arc<T>> {
+        Guard(self.load_full())
+    }
+
+    pub(crate) fn load_full(&self) -> Arc<T> {
+        loop {
+            let epoch = self.epoch.load(Ordering::SeqCst);
+            let readers = &self.readers[epoch % EPOCHS];
+            readers.fetch_add(1, Ordering::SeqCst);
+            let ptr = self.ptr.load(Ordering::SeqCst);
+            if self.epoch.load(Ordering::SeqCst) == epoch {
+                // SAFETY: `ptr` came from `Arc::into_raw`, and a store that
+                // swaps it out keeps that reference in `retired` while
+                // `readers` counts this load.
+                let loaded = unsafe {
+                    Arc::increment_strong_count(ptr);
+                    Arc::from_raw(ptr)
+                };
+                readers.fetch_sub(1, Ordering::Release);
+                return loaded;
+            }
+            // A store got in between, so `ptr` may belong to an epoch this
+            // load isn't counted in.
+            readers.fetch_sub(1, Ordering::Release);
+        }
+    }
+}
+
+#[cfg(all(not(feature = "std"), not(loom)))]
+impl<T> Drop for ArcSwap<T> {
+    fn drop(&mut self) {
+        // SAFETY: the pointer came from `Arc::into_raw` and the cell still
+        // owns that reference. `retired` drops along with the cell.
+        drop(unsafe { Arc::from_raw(*self.ptr.get_mut()) });
+    }
+}
+
+// SAFETY: the cell owns an `Arc<T>`, so it can cross and be shared between
+// threads exactly when `Arc<T>` can.
+#[cfg(all(not(feature = "std"), not(loom)))]
+unsafe impl<T: Send + Sync> Send for ArcSwap<T> {}
+#[cfg(all(not(feature = "std"), not(loom)))]
+unsafe impl<T: Send + Sync> Sync for ArcSwap<T> {}
+
+#[cfg(loom)]
+#[derive(Debug)]
+pub(crate) struct ArcSwap<T>(Mutex<Arc<T>>);
+
+#[cfg(loom)]
+impl<T> ArcSwap<T> {
+    pub(crate) fn new(value: Arc<T>) -> Self {
+        ArcSwap(Mutex::new(value))
+    }
+
+    pub(crate) fn store(&self, value: Arc<T>) {
+        *self.0.lock().unwrap() = value;
+    }
+
+    pub(crate) fn load(&self) -> Guard<Arc<T>> {
+        Guard(self.load_full())
+    }
+
+    pub(crate) fn load_full(&self) -> Arc<T> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+#[cfg(loom)]
+#[derive(Debug)]
+pub(crate) struct ArcSwapOption<T>(Mutex<Option<Arc<T>>>);
+
+#[cfg(loom)]
+impl<T> ArcSwapOption<T> {
+    pub(crate) fn empty() -> Self {
+        ArcSwapOption(Mutex::new(None))
+    }
+
+    pub(crate) fn store(&self, value: Option<Arc<T>>) {
+        *self.0.lock().unwrap() = value;
+    }
+
+    pub(crate) fn swap(&self, value: Option<Arc<T>>) -> Option<Arc<T>> {
+        std::mem::replace(&mut *self.0.lock().unwrap(), value)
+    }
+
+    pub(crate) fn load_full(&self) -> Option<Arc<T>> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+#[cfg(loom)]
+#[derive(Debug)]
+pub(crate) struct Weak<T>(Arc<T>);
+
+#[cfg(loom)]
+impl<T> Weak<T> {
+    pub(crate) fn upgrade(&self) -> Option<Arc<T>> {
+        Some(self.0.clone())
+    }
+}
+
+#[cfg(loom)]
+impl<T> Clone for Weak<T> {
+    fn clone(&self) -> Self {
+        Weak(self.0.clone())
+    }
+}
+
+#[cfg(loom)]
+pub(crate) fn downgrade<T>(arc: &Arc<T>) -> Weak<T> {
+    Weak(arc.clone())
+}
+
+/// Counts the readers pointing at `arc`, which under loom are every strong
+/// reference but the writer's.
+#[cfg(loom)]
+pub(crate) fn weak_count<T>(arc: &Arc<T>) -> usize {
+    Arc::strong_count(arc) - 1
+}
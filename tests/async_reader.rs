@@ -0,0 +1,76 @@
+use futures_core::Stream;
+use rio_thread_bench::channel;
+use rio_thread_bench::message::{Message, Payload};
+use std::future;
+use std::pin::Pin;
+use std::thread;
+use std::time::Duration;
+
+#[tokio::test]
+async fn get_new_wakes_on_publish_from_another_thread() {
+    let (mut writer, mut reader) = channel::<Message>(3);
+    let publisher = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(20));
+        writer.set(Message::new(1., -1.));
+        writer
+    });
+    let msg = tokio::time::timeout(Duration::from_secs(5), reader.get_new())
+        .await
+        .expect("get_new was never woken");
+    assert_eq!(msg.map(|msg| msg.y), Some(-1.));
+    drop(publisher.join().unwrap());
+    assert!(reader.get_new().await.is_none());
+}
+
+#[tokio::test]
+async fn get_new_coalesces_publishes_into_the_newest() {
+    let (mut writer, mut reader) = channel::<Message>(3);
+    for i in 1..=5 {
+        writer.set(Message::new(0., i as f64));
+    }
+    assert_eq!(reader.get_new().await.map(|msg| msg.y), Some(5.));
+}
+
+#[tokio::test]
+async fn watch_receivers_see_each_publish_until_the_writer_drops() {
+    let (mut writer, _reader) = channel::<Message>(3);
+    let mut rx = writer.watch();
+    let publisher = thread::spawn(move || {
+        writer.set(Message::new(0., 1.));
+        writer
+    });
+    tokio::time::timeout(Duration::from_secs(5), rx.changed())
+        .await
+        .expect("never saw the publish")
+        .unwrap();
+    assert_eq!(rx.borrow_and_update().y, 1.);
+    drop(publisher.join().unwrap());
+    assert!(rx.changed().await.is_err());
+    assert_eq!(rx.borrow().y, 1.);
+}
+
+/// Awaits the next item of `stream`, standing in for `StreamExt::next`.
+async fn next<S: Stream + Unpin>(stream: &mut S) -> Option<S::Item> {
+    future::poll_fn(|cx| Pin::new(&mut *stream).poll_next(cx)).await
+}
+
+#[tokio::test]
+async fn stream_coalesces_and_ends_when_the_writer_drops() {
+    let (mut writer, reader) = channel::<Message>(3);
+    let mut stream = reader.into_stream();
+    for i in 1..=3 {
+        writer.set(Message::new(0., i as f64));
+    }
+    assert_eq!(next(&mut stream).await.map(|msg| msg.y), Some(3.));
+    let publisher = thread::spawn(move || {
+        thread::sleep(Duration::from_millis(20));
+        writer.set(Message::new(0., 4.));
+        writer
+    });
+    let msg = tokio::time::timeout(Duration::from_secs(5), next(&mut stream))
+        .await
+        .expect("the stream was never woken");
+    assert_eq!(msg.map(|msg| msg.y), Some(4.));
+    drop(publisher.join().unwrap());
+    assert!(next(&mut stream).await.is_none());
+}
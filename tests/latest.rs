@@ -0,0 +1,1085 @@
+use bytes::Bytes;
+use rio_thread_bench::ffi::latest_reader_get;
+use rio_thread_bench::message::{Message, Payload};
+use rio_thread_bench::{
+    channel, snapshot_all, CapacityError, CasError, Latest, LatestBuilder, LatestReader,
+    OverflowPolicy, RateLimited, ReadError, ResizeError, SeqLatest, SetError, SharedWriter,
+    SpscLatest, WaitError,
+};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[test]
+fn set_with_every_slot_pinned_does_not_hang() {
+    let (mut writer, reader) = channel::<Message>(2);
+    let first = reader.get_arc().unwrap();
+    writer.set(Message::new(1., -1.));
+    let second = reader.get_arc().unwrap();
+    match writer.try_set(Message::new(2., -2.)) {
+        Err(SetError(msg)) => assert_eq!(msg.y, -2.),
+        Ok(()) => panic!("published into a pinned slot"),
+    }
+    drop(first);
+    assert!(writer.try_set(Message::new(3., -3.)).is_ok());
+    assert_eq!(second.y, -1.);
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn pinned_slots_count_as_retries() {
+    let (mut writer, reader) = channel::<Message>(3);
+    writer.set(Message::new(1., -1.));
+    assert_eq!(writer.retry_count(), 0);
+    let _pinned = reader.get_arc().unwrap();
+    for i in 2..=3 {
+        writer.set(Message::new(i as f64, 0.));
+    }
+    assert_eq!(writer.retry_count(), 0);
+    // Back round to the pinned slot, which gets skipped.
+    writer.set(Message::new(4., 0.));
+    assert_eq!(writer.retry_count(), 1);
+}
+
+#[test]
+fn try_new_rejects_rings_too_small_to_publish_into() {
+    assert_eq!(Latest::<Message>::try_new(0).unwrap_err(), CapacityError(0));
+    assert_eq!(Latest::<Message>::try_new(1).unwrap_err(), CapacityError(1));
+    assert_eq!(Latest::<Message>::try_new(2).unwrap().capacity(), 2);
+}
+
+#[test]
+fn with_capacity_pow2_rounds_up_and_wraps_round_the_masked_ring() {
+    assert_eq!(Latest::<Message>::with_capacity_pow2(2).capacity(), 2);
+    assert_eq!(Latest::<Message>::with_capacity_pow2(3).capacity(), 4);
+    assert_eq!(Latest::<Message>::with_capacity_pow2(5).capacity(), 8);
+    let mut writer = Latest::<Message>::with_capacity_pow2(4);
+    let reader = writer.reader();
+    writer.set(Message::new(1., 0.));
+    let pinned = reader.get_arc().unwrap();
+    for i in 2..=10 {
+        writer.set(Message::new(i as f64, 0.));
+        assert_eq!(reader.get().map(|msg| msg.x), Some(i as f64));
+    }
+    assert_eq!(pinned.x, 1.);
+    assert_eq!(writer.capacity(), 4);
+    #[cfg(feature = "metrics")]
+    {
+        // Slot 1 stays pinned at version 1, so each lap skips over it.
+        assert_eq!(writer.slot_versions(), [10, 1, 8, 9]);
+    }
+}
+
+#[test]
+fn wait_new_timeout_without_a_writer_times_out() {
+    let (_writer, mut reader) = channel::<Message>(2);
+    let start = Instant::now();
+    assert_eq!(
+        reader
+            .wait_new_timeout(Duration::from_millis(50))
+            .unwrap_err(),
+        WaitError::Timeout
+    );
+    let elapsed = start.elapsed();
+    assert!(elapsed >= Duration::from_millis(50));
+    assert!(elapsed < Duration::from_millis(500), "took {:?}", elapsed);
+}
+
+#[test]
+fn reader_is_closed_after_writer_drops() {
+    let (writer, reader) = channel::<Message>(2);
+    assert!(!reader.is_closed());
+    drop(writer);
+    assert!(reader.is_closed());
+    assert!(reader.get().is_none());
+}
+
+#[test]
+fn get_into_reuses_the_destination_buffer() {
+    let (mut writer, reader) = channel::<Vec<u32>>(3);
+    let mut dst = Vec::new();
+    writer.set(vec![1; 64]);
+    assert!(reader.get_into(&mut dst));
+    let buf = dst.as_ptr();
+    for i in 2..10 {
+        writer.set(vec![i; 64]);
+        assert!(reader.get_into(&mut dst));
+        assert_eq!(dst, [i; 64]);
+        assert_eq!(dst.as_ptr(), buf);
+    }
+    drop(writer);
+    assert!(!reader.get_into(&mut dst));
+}
+
+#[test]
+fn try_clone_fails_once_the_writer_is_gone() {
+    let (writer, reader) = channel::<Message>(2);
+    assert!(reader.try_clone().is_some());
+    drop(writer);
+    assert!(reader.try_clone().is_none());
+}
+
+#[test]
+fn current_version_counts_each_set() {
+    let (mut writer, reader) = channel::<Message>(2);
+    assert_eq!(writer.current_version(), 0);
+    for i in 1..=3 {
+        writer.set(Message::new(0., 0.));
+        assert_eq!(writer.current_version(), i);
+    }
+    assert_eq!(reader.version(), Some(writer.current_version()));
+}
+
+#[test]
+fn missed_count_counts_overwritten_values() {
+    let (mut writer, mut reader) = channel::<Message>(2);
+    writer.set(Message::new(0., 0.));
+    assert!(reader.get_if_changed().is_some());
+    for i in 1..=10 {
+        writer.set(Message::new(0., i as f64));
+    }
+    assert_eq!(reader.missed_count(), 9);
+    assert_eq!(reader.get_if_changed().map(|msg| msg.y), Some(10.));
+    assert_eq!(reader.missed_count(), 0);
+}
+
+#[test]
+fn reader_count_tracks_live_readers() {
+    let (writer, first) = channel::<Message>(2);
+    let mut readers = writer.readers(4);
+    assert_eq!(writer.reader_count(), 5);
+    readers.truncate(1);
+    assert_eq!(writer.reader_count(), 2);
+    drop(first);
+    drop(readers);
+    assert_eq!(writer.reader_count(), 0);
+}
+
+#[test]
+fn cloned_readers_are_counted_until_dropped() {
+    let (writer, reader) = channel::<Message>(2);
+    let clones: Vec<_> = (0..3).map(|_| reader.clone()).collect();
+    let mapped = clones[0].clone().map(|msg| msg.y);
+    assert_eq!(writer.reader_count(), 5);
+    drop(mapped);
+    drop(clones);
+    assert_eq!(writer.reader_count(), 1);
+    drop(reader);
+    assert_eq!(writer.reader_count(), 0);
+}
+
+#[test]
+fn mapped_reader_projects_a_single_field() {
+    let (mut writer, reader) = channel::<Message>(2);
+    let y = reader.map(|msg| msg.y);
+    writer.set(Message::new(1., -1.));
+    assert_eq!(y.get(), Some(-1.));
+}
+
+#[test]
+fn shared_writers_publish_concurrently() {
+    let writer = SharedWriter::<Message>::new(4);
+    let reader = writer.reader();
+    let writers: Vec<_> = (0..2)
+        .map(|w| {
+            let writer = writer.clone();
+            thread::spawn(move || {
+                for i in 0..10000 {
+                    writer.set(Message::new(w as f64, i as f64));
+                }
+            })
+        })
+        .collect();
+    for _ in 0..10000 {
+        let msg = reader.get().unwrap();
+        assert!(msg.x == 0. || msg.x == 1.);
+    }
+    for writer in writers {
+        writer.join().unwrap();
+    }
+    assert_eq!(reader.version(), Some(20000));
+    assert_eq!(reader.get().unwrap().y, 9999.);
+    drop(writer);
+    assert!(reader.is_closed());
+}
+
+#[test]
+fn growable_ring_grows_past_pinned_slots() {
+    let mut writer = Latest::<Message>::growable(2, 3);
+    let reader = writer.reader();
+    let _first = reader.get_arc().unwrap();
+    writer.set(Message::new(1., -1.));
+    let _second = reader.get_arc().unwrap();
+    writer.set(Message::new(2., -2.));
+    assert_eq!(writer.capacity(), 3);
+    assert_eq!(reader.get().map(|msg| msg.y), Some(-2.));
+    let _third = reader.get_arc().unwrap();
+    assert!(writer.try_set(Message::new(3., -3.)).is_err());
+}
+
+#[test]
+fn reclaim_shrinks_a_grown_ring_back_down() {
+    let mut writer = Latest::<Message>::growable(2, 4);
+    let reader = writer.reader();
+    let pinned: Vec<_> = (1..=3)
+        .map(|i| {
+            let held = reader.get_arc().unwrap();
+            writer.set(Message::new(0., i as f64));
+            held
+        })
+        .collect();
+    assert_eq!(writer.capacity(), 4);
+    drop(pinned);
+    writer.set(Message::new(0., 4.));
+    assert_eq!(writer.reclaim(), 2);
+    assert_eq!(writer.capacity(), 2);
+    assert_eq!(writer.free_slots(), 1);
+    assert_eq!(reader.get().map(|msg| msg.y), Some(4.));
+}
+
+#[test]
+fn reclaim_stale_frees_payloads_readers_are_done_with() {
+    let (mut writer, reader) = channel::<Option<Arc<Vec<u8>>>>(3);
+    let payload = Arc::new(vec![0; 1 << 20]);
+    let freed = Arc::downgrade(&payload);
+    writer.set(Some(payload));
+    let held = reader.get_arc().unwrap();
+    writer.set(None);
+    drop(held);
+    assert!(freed.upgrade().is_some());
+    assert_eq!(writer.reclaim_stale(), 2);
+    assert!(freed.upgrade().is_none());
+    assert_eq!(reader.get(), Some(None));
+}
+
+#[test]
+fn subscribe_hands_back_the_current_value_as_seen() {
+    let (mut writer, _reader) = channel::<Message>(2);
+    for i in 1..=3 {
+        writer.set(Message::new(0., i as f64));
+    }
+    let (mut late, current) = writer.subscribe();
+    assert_eq!(current.y, 3.);
+    assert!(late.get_if_changed().is_none());
+    writer.set(Message::new(0., 4.));
+    assert_eq!(late.get_if_changed().map(|msg| msg.y), Some(4.));
+}
+
+#[test]
+fn iter_new_yields_the_pending_value_then_stops() {
+    let (mut writer, mut reader) = channel::<Message>(2);
+    writer.set(Message::new(0., 1.));
+    writer.set(Message::new(0., 2.));
+    let seen: Vec<_> = reader.iter_new().map(|msg| msg.y).collect();
+    assert_eq!(seen, [2.]);
+    assert_eq!(reader.iter_new().count(), 0);
+}
+
+#[test]
+fn resize_keeps_the_current_value() {
+    let (mut writer, mut reader) = channel::<Message>(2);
+    writer.set(Message::new(0., 1.));
+    writer.resize(6).unwrap();
+    assert_eq!(writer.capacity(), 6);
+    assert_eq!(reader.get_if_changed().map(|msg| msg.y), Some(1.));
+    for i in 2..=4 {
+        writer.set(Message::new(0., i as f64));
+    }
+    // The current value is in the fifth slot.
+    assert_eq!(writer.resize(4), Err(ResizeError::Pinned));
+    writer.resize(5).unwrap();
+    assert_eq!(writer.capacity(), 5);
+    assert_eq!(writer.resize(1), Err(ResizeError::TooSmall));
+    assert_eq!(writer.current_version(), 4);
+    assert_eq!(reader.get_if_changed().map(|msg| msg.y), Some(4.));
+}
+
+#[test]
+fn peek_leaves_a_change_pending() {
+    let (mut writer, mut reader) = channel::<Message>(2);
+    writer.set(Message::new(1., -1.));
+    assert_eq!(reader.peek().map(|msg| msg.y), Some(-1.));
+    assert_eq!(reader.get_if_changed().map(|msg| msg.y), Some(-1.));
+    assert!(reader.get_if_changed().is_none());
+}
+
+#[test]
+fn dropping_the_writer_unblocks_wait_closed() {
+    let (writer, reader) = channel::<Message>(2);
+    let waiter = thread::spawn(move || {
+        let start = Instant::now();
+        reader.wait_closed();
+        start.elapsed()
+    });
+    thread::sleep(Duration::from_millis(20));
+    drop(writer);
+    assert!(waiter.join().unwrap() < Duration::from_secs(5));
+}
+
+#[test]
+fn get_versioned_pairs_each_value_with_its_version() {
+    let (mut writer, reader) = channel::<Message>(4);
+    let publisher = thread::spawn(move || {
+        for i in 1..=10000 {
+            writer.set(Message::new(i as f64, 0.));
+        }
+    });
+    while let Some((msg, version)) = reader.get_versioned() {
+        assert_eq!(msg.x, version as f64);
+    }
+    publisher.join().unwrap();
+}
+
+trait Reading: Send + Sync {
+    fn value(&self) -> f64;
+}
+
+struct Celsius(f64);
+
+impl Reading for Celsius {
+    fn value(&self) -> f64 {
+        self.0
+    }
+}
+
+struct Fahrenheit(f64);
+
+impl Reading for Fahrenheit {
+    fn value(&self) -> f64 {
+        (self.0 - 32.) * 5. / 9.
+    }
+}
+
+#[test]
+fn trait_objects_publish_through_an_arc() {
+    let mut writer = Latest::<Arc<dyn Reading>>::with_initial(2, Arc::new(Celsius(0.)));
+    let reader = writer.reader();
+    writer.set(Arc::new(Fahrenheit(212.)));
+    let reading = reader.get().unwrap();
+    assert_eq!(reading.value(), 100.);
+    writer.set(Arc::new(Celsius(-40.)));
+    assert_eq!(reader.get().unwrap().value(), -40.);
+    assert_eq!(reading.value(), 100.);
+}
+
+#[test]
+fn set_and_take_recycles_the_previous_value() {
+    let (mut writer, reader) = channel::<Message>(2);
+    writer.set(Message::new(1., -1.));
+    let recycled = writer.set_and_take(Message::new(2., -2.)).unwrap();
+    assert_eq!(recycled.y, -1.);
+    assert_eq!(reader.get().map(|msg| msg.y), Some(-2.));
+    let _held = reader.get_arc().unwrap();
+    assert!(writer.set_and_take(recycled).is_none());
+    assert_eq!(reader.get().map(|msg| msg.y), Some(-1.));
+}
+
+#[test]
+fn every_reader_converges_on_the_last_value() {
+    const LAST: u64 = 10000;
+    // Room for each reader to be holding a value mid-read, so `set` always
+    // finds a slot.
+    let (mut writer, reader) = channel::<Message>(6);
+    let readers: Vec<_> = writer
+        .readers(4)
+        .into_iter()
+        .map(|reader| {
+            thread::spawn(move || {
+                let mut seen = 0;
+                while seen < LAST {
+                    let (msg, version) = reader.get_versioned().unwrap();
+                    assert!(version >= seen, "went back from {} to {}", seen, version);
+                    assert_eq!(msg.x, version as f64);
+                    seen = version;
+                }
+                reader
+            })
+        })
+        .collect();
+    // The writer is moved back out so that it outlives the readers' checks.
+    let writer = thread::spawn(move || {
+        for i in 1..=LAST {
+            writer.set(Message::new(i as f64, 0.));
+        }
+        writer
+    })
+    .join()
+    .unwrap();
+    for reader in readers {
+        let reader = reader.join().unwrap();
+        assert_eq!(reader.get().map(|msg| msg.x), Some(LAST as f64));
+    }
+    assert_eq!(reader.get().map(|msg| msg.x), Some(LAST as f64));
+    drop(writer);
+}
+
+#[test]
+fn on_publish_sees_every_published_value() {
+    let (mut writer, _reader) = channel::<Message>(2);
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let log = seen.clone();
+    writer.on_publish(move |msg| log.lock().unwrap().push(msg.y));
+    for i in 1..=5 {
+        writer.set(Message::new(0., i as f64));
+    }
+    assert_eq!(*seen.lock().unwrap(), [1., 2., 3., 4., 5.]);
+}
+
+#[test]
+fn set_with_edits_a_free_slot_in_place() {
+    let (mut writer, reader) = channel(2);
+    writer.set_with(|msg: &mut Vec<u32>| msg.push(1));
+    assert_eq!(reader.get(), Some(vec![1]));
+    // The only free slot still holds the initial empty value.
+    writer.set_with(|msg| msg.push(2));
+    assert_eq!(reader.get(), Some(vec![2]));
+    assert_eq!(reader.version(), Some(2));
+}
+
+#[test]
+fn poisoned_latest_fails_try_get() {
+    let (mut writer, reader) = channel::<Message>(2);
+    assert!(reader.try_get().is_ok());
+    writer.poison("sensor disconnected");
+    writer.poison("ignored");
+    assert_eq!(reader.try_get(), Err(ReadError::Poisoned));
+    assert_eq!(
+        reader.poison_reason().as_deref(),
+        Some("sensor disconnected")
+    );
+    drop(writer);
+    assert_eq!(reader.try_get(), Err(ReadError::Closed));
+}
+
+#[test]
+fn debug_sums_up_the_ring_unless_alternate() {
+    let (mut writer, _reader) = channel::<Message>(3);
+    writer.set(Message::new(0., 1.));
+    assert_eq!(
+        format!("{:?}", writer),
+        "Latest { capacity: 3, version: 1, free_slots: 2, readers: 1 }"
+    );
+    assert!(format!("{:#?}", writer).contains("dummy"));
+}
+
+/// Builds a two-slot `Latest` with `policy` and pins both slots, returning
+/// the pins along with it.
+fn saturated(policy: OverflowPolicy) -> (Latest<Message>, Vec<Arc<Message>>) {
+    let mut writer = Latest::<Message>::growable(2, 3).with_overflow(policy);
+    let reader = writer.reader();
+    let mut pins = vec![reader.get_arc().unwrap()];
+    writer.set(Message::new(1., -1.));
+    pins.push(reader.get_arc().unwrap());
+    (writer, pins)
+}
+
+#[test]
+fn overwrite_policy_grows_a_growable_ring() {
+    let (mut writer, _pins) = saturated(OverflowPolicy::Overwrite);
+    assert!(writer.try_set(Message::new(2., -2.)).is_ok());
+    assert_eq!(writer.capacity(), 3);
+}
+
+#[test]
+fn drop_policy_skips_the_publish() {
+    let (mut writer, _pins) = saturated(OverflowPolicy::Drop);
+    assert!(writer.try_set(Message::new(2., -2.)).is_ok());
+    writer.set(Message::new(3., -3.));
+    assert_eq!(writer.capacity(), 2);
+    assert_eq!(writer.current_version(), 1);
+}
+
+#[test]
+fn set_if_changed_reports_a_value_the_drop_policy_dropped() {
+    let (mut writer, _pins) = saturated(OverflowPolicy::Drop);
+    assert!(!writer.set_if_changed(Message::new(2., -2.)));
+    assert_eq!(writer.current_version(), 1);
+}
+
+#[test]
+fn set_many_publishes_only_the_last_of_a_batch() {
+    let (mut writer, mut reader) = channel::<Message>(2);
+    writer.set_many((1..=5).map(|i| Message::new(i as f64, 0.)));
+    assert_eq!(writer.current_version(), 1);
+    assert_eq!(reader.get_if_changed().map(|msg| msg.x), Some(5.));
+    writer.set_many(std::iter::empty());
+    assert_eq!(writer.current_version(), 1);
+    assert!(reader.get_if_changed().is_none());
+    assert_eq!(reader.get().map(|msg| msg.x), Some(5.));
+}
+
+#[test]
+fn set_if_changed_skips_an_equal_value_and_publishes_a_different_one() {
+    let (mut writer, mut reader) = channel::<Message>(2);
+    writer.set(Message::new(1., -1.));
+    assert!(reader.get_if_changed().is_some());
+    let waiter = thread::spawn(move || {
+        let woken = reader.wait_new_timeout(Duration::from_millis(100));
+        (reader, woken)
+    });
+    assert!(!writer.set_if_changed(Message::new(1., -1.)));
+    assert_eq!(writer.current_version(), 1);
+    let (mut reader, woken) = waiter.join().unwrap();
+    assert_eq!(woken.unwrap_err(), WaitError::Timeout);
+    assert!(writer.set_if_changed(Message::new(2., -2.)));
+    assert_eq!(writer.current_version(), 2);
+    assert_eq!(reader.get_if_changed().map(|msg| msg.x), Some(2.));
+}
+
+#[test]
+fn rate_limited_does_not_count_a_dropped_value_as_published() {
+    let (writer, pins) = saturated(OverflowPolicy::Drop);
+    let mut limited = RateLimited::new(writer, Duration::from_secs(60));
+    assert!(!limited.set(Message::new(2., -2.)));
+    assert_eq!(limited.current_version(), 1);
+    drop(pins);
+    assert!(limited.set(Message::new(3., -3.)));
+    assert_eq!(limited.current_version(), 2);
+}
+
+#[test]
+fn error_policy_hands_the_value_back_without_growing() {
+    let (mut writer, _pins) = saturated(OverflowPolicy::Error);
+    match writer.try_set(Message::new(2., -2.)) {
+        Err(SetError(msg)) => assert_eq!(msg.y, -2.),
+        Ok(()) => panic!("published into a saturated ring"),
+    }
+    assert_eq!(writer.capacity(), 2);
+}
+
+#[test]
+fn get_or_default_falls_back_once_the_writer_is_dropped() {
+    let (mut writer, reader) = channel::<Message>(2);
+    writer.set(Message::new(1., -1.));
+    assert_eq!(reader.get_or_default().y, -1.);
+    drop(writer);
+    assert_eq!(reader.get_or_default(), Message::default());
+}
+
+#[test]
+fn scoped_threads_can_publish_borrowed_values() {
+    let names = [String::from("a"), String::from("b"), String::from("c")];
+    let (mut writer, mut reader) = channel::<&str>(3);
+    thread::scope(|s| {
+        s.spawn(|| {
+            for name in &names {
+                writer.set(name);
+            }
+        });
+        s.spawn(|| while reader.wait_new() != Some("c") {});
+    });
+    assert_eq!(reader.get(), Some("c"));
+}
+
+#[test]
+fn compare_and_set_fails_on_a_stale_version() {
+    let (mut writer, reader) = channel::<Message>(2);
+    assert_eq!(writer.compare_and_set(0, Message::new(1., -1.)), Ok(1));
+    assert_eq!(
+        writer.compare_and_set(0, Message::new(2., -2.)),
+        Err(CasError::Moved(1))
+    );
+    assert_eq!(reader.get().map(|msg| msg.y), Some(-1.));
+
+    let shared = SharedWriter::<Message>::new(3);
+    let other = shared.clone();
+    assert_eq!(shared.compare_and_set(0, Message::new(1., -1.)), Ok(1));
+    assert_eq!(
+        other.compare_and_set(0, Message::new(2., -2.)),
+        Err(CasError::Moved(1))
+    );
+    assert_eq!(other.compare_and_set(1, Message::new(2., -2.)), Ok(2));
+}
+
+#[test]
+fn compare_and_set_follows_the_overflow_policy() {
+    let (mut writer, _pins) = saturated(OverflowPolicy::Overwrite);
+    assert_eq!(writer.compare_and_set(1, Message::new(2., -2.)), Ok(2));
+    assert_eq!(writer.capacity(), 3);
+
+    let (mut writer, _pins) = saturated(OverflowPolicy::Drop);
+    assert_eq!(
+        writer.compare_and_set(1, Message::new(2., -2.)),
+        Err(CasError::Dropped)
+    );
+    assert_eq!(writer.current_version(), 1);
+}
+
+#[test]
+#[should_panic(expected = "every slot in the ring is held by a reader")]
+fn compare_and_set_panics_on_a_saturated_ring_under_the_error_policy() {
+    let (mut writer, _pins) = saturated(OverflowPolicy::Error);
+    let _ = writer.compare_and_set(1, Message::new(2., -2.));
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn slot_versions_follow_the_writer_round_the_ring() {
+    let (mut writer, _reader) = channel::<Message>(3);
+    assert_eq!(writer.slot_versions(), [0, 0, 0]);
+    for i in 1..=4 {
+        writer.set(Message::new(i as f64, 0.));
+    }
+    assert_eq!(writer.slot_versions(), [3, 4, 2]);
+    assert_eq!(writer.reclaim_stale(), 2);
+    assert_eq!(writer.slot_versions(), [0, 4, 0]);
+}
+
+#[cfg(feature = "metrics")]
+#[test]
+fn set_and_take_moves_the_stale_value_with_its_version() {
+    let (mut writer, _reader) = channel::<Message>(3);
+    writer.set(Message::new(1., 0.));
+    writer.set(Message::new(2., 0.));
+    assert_eq!(writer.slot_versions(), [0, 1, 2]);
+    let taken = writer.set_and_take(Message::new(3., 0.)).unwrap();
+    assert_eq!(taken.x, 2.);
+    assert_eq!(writer.slot_versions(), [3, 1, 0]);
+}
+
+#[test]
+fn bytes_payloads_are_read_without_copying_the_buffer() {
+    let (mut writer, reader) = channel::<Bytes>(2);
+    let packet = Bytes::from(vec![7; 1500]);
+    writer.set(packet.clone());
+    let read = reader.get().unwrap();
+    assert_eq!(read.as_ptr(), packet.as_ptr());
+    assert_eq!(read, packet);
+}
+
+#[test]
+fn cloned_latest_is_independent_of_the_original() {
+    let (mut writer, reader) = channel::<Message>(3);
+    writer.set(Message::new(1., -1.));
+    let mut fork = writer.clone();
+    assert_eq!(fork.reader().get().map(|msg| msg.y), Some(-1.));
+    assert_eq!(fork.reader_count(), 0);
+    fork.set(Message::new(2., -2.));
+    assert_eq!(reader.get().map(|msg| msg.y), Some(-1.));
+    assert_eq!(reader.version(), Some(1));
+}
+
+#[test]
+fn from_values_preloads_the_ring() {
+    assert_eq!(Latest::from_values(vec![1]).unwrap_err(), CapacityError(1));
+    let mut writer = Latest::from_values(vec![1, 2, 3]).unwrap();
+    let mut reader = writer.reader();
+    assert_eq!(writer.current_version(), 3);
+    assert_eq!(reader.get_if_changed(), Some(3));
+    // The writer carries on from the last value, overwriting the first.
+    writer.set(4);
+    assert_eq!(reader.get_versioned(), Some((4, 4)));
+}
+
+#[test]
+fn poll_coalesced_counts_every_publish_since_the_last_poll() {
+    let (mut writer, mut reader) = channel::<Message>(2);
+    assert!(reader.poll_coalesced().is_none());
+    for i in 1..=5 {
+        writer.set(Message::new(i as f64, 0.));
+    }
+    let (msg, published) = reader.poll_coalesced().unwrap();
+    assert_eq!((msg.x, published), (5., 5));
+    assert!(reader.poll_coalesced().is_none());
+}
+
+#[test]
+fn readers_and_writers_can_cross_threads() {
+    fn send_sync<T: Send + Sync>() {}
+    send_sync::<Latest<Message>>();
+    send_sync::<LatestReader<Message>>();
+    send_sync::<SharedWriter<Message>>();
+    send_sync::<LatestReader<Arc<str>>>();
+}
+
+#[test]
+fn rate_limited_publishes_about_once_per_interval() {
+    let mut writer = RateLimited::new(Latest::<Message>::new(2), Duration::from_millis(20));
+    let start = Instant::now();
+    let mut published = 0;
+    while start.elapsed() < Duration::from_millis(100) {
+        if writer.set(Message::new(0., 0.)) {
+            published += 1;
+        }
+    }
+    // One at the start and one per interval after, give or take scheduling.
+    assert!((3..=6).contains(&published), "published {}", published);
+    assert_eq!(writer.current_version(), published);
+}
+
+#[test]
+fn owning_payloads_need_not_be_copy() {
+    let (mut writer, mut reader) = channel::<String>(2);
+    writer.set(String::from("first"));
+    assert_eq!(reader.get_if_changed().as_deref(), Some("first"));
+    writer.set_with(|msg| msg.push_str("second"));
+    assert_eq!(reader.get().as_deref(), Some("second"));
+}
+
+#[cfg(feature = "latency")]
+#[test]
+fn last_latency_covers_the_wait_before_reading() {
+    let (mut writer, mut reader) = channel::<Message>(2);
+    assert_eq!(reader.last_latency(), None);
+    writer.set(Message::new(1., -1.));
+    thread::sleep(Duration::from_millis(50));
+    assert!(reader.get_if_changed().is_some());
+    let latency = reader.last_latency().unwrap();
+    assert!(latency >= Duration::from_millis(50), "{:?}", latency);
+    assert!(latency < Duration::from_secs(1), "{:?}", latency);
+}
+
+#[test]
+fn strong_reader_keeps_the_last_value_after_the_writer_drops() {
+    let (mut writer, reader) = channel::<Message>(2);
+    let strong = writer.strong_reader();
+    writer.set(Message::new(1., -1.));
+    assert_eq!(writer.reader_count(), 1);
+    drop(writer);
+    assert_eq!(reader.get(), None);
+    assert!(strong.is_closed());
+    assert_eq!(strong.get().y, -1.);
+    assert_eq!(strong.version(), 1);
+}
+
+#[test]
+fn get_if_changed_sees_publishes_across_the_version_wrap() {
+    let max = u64::MAX >> 1;
+    let mut writer = Latest::<Message>::new(2).with_version(max - 1);
+    let mut reader = writer.reader();
+    assert!(reader.get_if_changed().is_none());
+    writer.set(Message::new(1., 0.));
+    assert_eq!(
+        reader.get_versioned().map(|(_, version)| version),
+        Some(max)
+    );
+    assert_eq!(reader.get_if_changed().map(|msg| msg.x), Some(1.));
+    writer.set(Message::new(2., 0.));
+    writer.set(Message::new(3., 0.));
+    assert_eq!(writer.current_version(), 1);
+    assert_eq!(reader.missed_count(), 1);
+    assert_eq!(
+        reader.poll_coalesced().map(|(msg, n)| (msg.x, n)),
+        Some((3., 2))
+    );
+}
+
+#[test]
+fn snapshot_all_reads_each_reader_once() {
+    let mut first = Latest::<Message>::new(2);
+    let second = Latest::<Message>::new(2);
+    let readers = [first.reader(), second.reader(), second.reader()];
+    first.set(Message::new(1., 0.));
+    drop(second);
+    let xs: Vec<_> = snapshot_all(&readers)
+        .into_iter()
+        .map(|msg| msg.map(|msg| msg.x))
+        .collect();
+    assert_eq!(xs, [Some(1.), None, None]);
+}
+
+#[test]
+fn latest_reader_get_copies_out_through_the_c_abi() {
+    let (mut writer, reader) = channel::<Message>(2);
+    writer.set(Message::new(1., 2.));
+    let mut out = <Message>::default();
+    assert!(unsafe { latest_reader_get(&reader, &mut out) });
+    assert_eq!(out, Message::new(1., 2.));
+    drop(writer);
+    assert!(!unsafe { latest_reader_get(&reader, &mut out) });
+    assert_eq!(out, Message::new(1., 2.));
+}
+
+/// The number every `Secret` held when it was dropped. Each test using it
+/// keeps to its own range of numbers, since tests run at the same time.
+#[cfg(feature = "zeroize")]
+static DROPPED_SECRETS: Mutex<Vec<u64>> = Mutex::new(Vec::new());
+
+/// A payload that records what it holds in `DROPPED_SECRETS` when dropped, so
+/// a test can tell whether it was zeroed first.
+#[cfg(feature = "zeroize")]
+#[derive(Default, Clone)]
+struct Secret(u64);
+
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for Secret {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Secret {
+    fn drop(&mut self) {
+        DROPPED_SECRETS.lock().unwrap().push(self.0);
+    }
+}
+
+#[cfg(feature = "zeroize")]
+#[test]
+fn zero_on_reclaim_clears_a_value_before_its_slot_is_reused() {
+    let mut writer = Latest::<Secret>::new(2).with_zero_on_reclaim();
+    writer.set(Secret(101));
+    writer.set(Secret(102));
+    writer.set(Secret(103));
+    assert!(!DROPPED_SECRETS.lock().unwrap().contains(&101));
+    assert_eq!(writer.reader().get().map(|secret| secret.0), Some(103));
+}
+
+#[cfg(feature = "zeroize")]
+#[test]
+fn zero_on_reclaim_clears_the_slots_only_the_ring_holds_on_drop() {
+    let mut writer = Latest::<Secret>::new(3).with_zero_on_reclaim();
+    writer.set(Secret(201));
+    writer.set(Secret(202));
+    drop(writer);
+    let dropped = DROPPED_SECRETS.lock().unwrap();
+    assert!(!dropped.contains(&201));
+    // The current value is the shared cell's, so it goes as it is.
+    assert!(dropped.contains(&202));
+}
+
+#[test]
+fn reader_lags_differ_with_how_often_readers_read() {
+    let mut writer = Latest::<Message>::new(3);
+    assert!(writer.reader_lags().is_empty());
+    let mut fast = writer.reader();
+    let mut slow = writer.reader();
+    let gone = writer.reader();
+    drop(gone);
+    for i in 0..5 {
+        writer.set(Message::new(i as f64, 0.));
+        assert!(fast.get_if_changed().is_some());
+        if i == 1 {
+            assert!(slow.get_if_changed().is_some());
+        }
+    }
+    assert_eq!(writer.reader_lags(), [0, 3]);
+}
+
+#[test]
+fn reader_lags_track_an_earlier_reader_once_it_marks_a_value() {
+    let mut writer = Latest::<Message>::new(3);
+    let mut early = writer.reader();
+    writer.set(Message::new(1., 0.));
+    assert!(early.get_if_changed().is_some());
+    assert!(writer.reader_lags().is_empty());
+    let _late = writer.reader();
+    writer.set(Message::new(2., 0.));
+    assert_eq!(writer.reader_lags(), [2]);
+    assert!(early.get_if_changed().is_some());
+    assert_eq!(writer.reader_lags(), [2, 0]);
+}
+
+#[cfg(feature = "debug-invariants")]
+#[test]
+#[should_panic(expected = "a publish was left in flight")]
+fn debug_invariants_catch_a_corrupted_ring() {
+    let mut writer = Latest::<Message>::new(2);
+    writer.set(Message::new(1., 0.));
+    writer.debug_leave_publish_in_flight();
+    writer.set(Message::new(2., 0.));
+}
+
+#[test]
+fn builder_options_take_effect() {
+    let mut latest = LatestBuilder::new(<Message>::new(1., 0.))
+        .capacity(3)
+        .round_up_to_pow2()
+        .growable(6)
+        .overflow_policy(OverflowPolicy::Error)
+        .build();
+    assert_eq!(latest.capacity(), 4);
+    assert_eq!(latest.reader().get().map(|msg| msg.x), Some(1.));
+    let held: Vec<_> = (0..4)
+        .map(|i| {
+            latest.set(Message::new(i as f64, 0.));
+            latest.reader().get_arc()
+        })
+        .collect();
+    assert!(latest.try_set(Message::new(4., 0.)).is_err());
+    assert_eq!(latest.capacity(), 4);
+    drop(held);
+
+    let mut grows = Latest::<Message>::builder().growable(3).build();
+    let _held = grows.reader().get_arc();
+    grows.set(Message::new(1., 0.));
+    let _held = grows.reader().get_arc();
+    grows.set(Message::new(2., 0.));
+    assert_eq!(grows.capacity(), 3);
+    #[cfg(feature = "metrics")]
+    {
+        let mut quiet = Latest::<Message>::builder().with_metrics(false).build();
+        quiet.set(Message::new(1., 0.));
+        assert_eq!(quiet.slot_versions(), [0, 0]);
+    }
+}
+
+#[test]
+fn guard_keeps_the_value_it_borrows_alive() {
+    let (mut writer, reader) = channel::<Message>(3);
+    writer.set(Message::new(1., -1.));
+    let guard = reader.guard().unwrap();
+    assert_eq!(guard.x, 1.);
+    writer.set(Message::new(2., -2.));
+    writer.set(Message::new(3., -3.));
+    assert_eq!(*guard, Message::new(1., -1.));
+    assert_eq!(reader.guard().unwrap().x, 3.);
+    drop(writer);
+    assert!(reader.guard().is_none());
+    assert_eq!(guard.y, -1.);
+}
+
+#[test]
+fn map_guard_borrows_a_field_in_place() {
+    let (mut writer, reader) = channel::<Message>(3);
+    writer.set(Message::new(1., 0.));
+    let dummy = reader.map_guard(|msg| &msg.dummy).unwrap();
+    assert_eq!(dummy.as_ptr(), reader.get_arc().unwrap().dummy.as_ptr());
+    writer.set(Message::new(2., 0.));
+    writer.set(Message::new(3., 0.));
+    assert_eq!(*dummy, [50; 80]);
+    let x = reader.map_guard(|msg| &msg.x).unwrap();
+    assert_eq!(*x, 3.);
+    drop(writer);
+    assert!(reader.map_guard(|msg| &msg.dummy).is_none());
+}
+
+#[test]
+fn seq_latest_round_trips_until_the_writer_drops() {
+    let mut writer = SeqLatest::new((0u64, 0u64));
+    let reader = writer.reader();
+    assert_eq!(reader.get(), Some((0, 0)));
+    writer.set((1, 1));
+    assert_eq!(reader.get(), Some((1, 1)));
+    assert_eq!(reader.clone().get(), Some((1, 1)));
+    drop(writer);
+    assert_eq!(reader.get(), None);
+}
+
+/// A payload whose fields all hold the same number, so that a read mixing two
+/// writes shows up as fields that disagree.
+#[derive(Debug, Clone, Copy)]
+struct Mirrored([u64; 16]);
+
+impl Mirrored {
+    fn new(i: u64) -> Self {
+        Mirrored([i; 16])
+    }
+
+    fn is_whole(&self) -> bool {
+        self.0.iter().all(|&field| field == self.0[0])
+    }
+}
+
+#[test]
+fn seq_reader_never_sees_a_torn_value() {
+    let mut writer = SeqLatest::new(Mirrored::new(0));
+    let reader = writer.reader();
+    let done = std::sync::atomic::AtomicBool::new(false);
+    thread::scope(|s| {
+        for _ in 0..2 {
+            let reader = reader.clone();
+            let done = &done;
+            s.spawn(move || {
+                let mut last = 0;
+                for _ in 0..100_000 {
+                    let value = reader.get().unwrap();
+                    assert!(value.is_whole(), "torn read: {:?}", value);
+                    assert!(value.0[0] >= last);
+                    last = value.0[0];
+                }
+                done.store(true, std::sync::atomic::Ordering::Relaxed);
+            });
+        }
+        let mut i = 0;
+        while !done.load(std::sync::atomic::Ordering::Relaxed) {
+            i += 1;
+            writer.set(Mirrored::new(i));
+        }
+    });
+}
+
+#[test]
+fn spsc_hands_out_one_reader_that_sees_the_writer_close() {
+    let mut writer = SpscLatest::new(0u64);
+    let mut reader = writer.reader().unwrap();
+    assert!(writer.reader().is_none());
+    assert_eq!(reader.get(), Some(&0));
+    writer.set(1);
+    writer.set(2);
+    assert_eq!(reader.get(), Some(&2));
+    drop(writer);
+    assert_eq!(reader.get(), None);
+}
+
+#[test]
+fn spsc_reads_only_move_forward() {
+    let mut writer = SpscLatest::new(Mirrored::new(0));
+    let mut reader = writer.reader().unwrap();
+    let done = std::sync::atomic::AtomicBool::new(false);
+    thread::scope(|s| {
+        s.spawn(|| {
+            let mut last = 0;
+            for _ in 0..100_000 {
+                let value = reader.get().unwrap();
+                assert!(value.is_whole(), "torn read: {:?}", value);
+                assert!(value.0[0] >= last);
+                last = value.0[0];
+            }
+            done.store(true, std::sync::atomic::Ordering::Relaxed);
+        });
+        let mut i = 0;
+        while !done.load(std::sync::atomic::Ordering::Relaxed) {
+            i += 1;
+            writer.set(Mirrored::new(i));
+        }
+    });
+}
+
+#[test]
+fn history_snapshot_is_oldest_first() {
+    let mut writer = Latest::<u64>::new(3);
+    let history = writer.history_reader();
+    assert_eq!(history.snapshot(), [0]);
+    writer.set(1);
+    writer.set(2);
+    assert_eq!(history.snapshot(), [0, 1, 2]);
+    writer.set(3);
+    assert_eq!(history.snapshot(), [1, 2, 3]);
+}
+
+#[test]
+fn history_snapshot_skips_values_lapped_around_a_pinned_slot() {
+    let mut writer = Latest::<u64>::new(3);
+    let history = writer.history_reader();
+    let reader = writer.reader();
+    writer.set(1);
+    let _pinned = reader.get_arc();
+    for i in 2..=4 {
+        writer.set(i);
+    }
+    assert_eq!(history.snapshot(), [1, 3, 4]);
+}
+
+#[test]
+fn history_snapshot_covers_slots_a_growable_ring_adds() {
+    let mut writer = Latest::<u64>::growable(2, 4);
+    let history = writer.history_reader();
+    let reader = writer.reader();
+    let _first = reader.get_arc();
+    writer.set(1);
+    let _second = reader.get_arc();
+    writer.set(2);
+    assert_eq!(writer.capacity(), 3);
+    assert_eq!(history.snapshot(), [0, 1, 2]);
+}
+
+#[test]
+fn history_snapshot_is_empty_once_the_writer_drops() {
+    let mut writer = Latest::<u64>::new(2);
+    let history = writer.history_reader();
+    writer.set(1);
+    drop(writer);
+    assert!(history.snapshot().is_empty());
+}
@@ -0,0 +1,45 @@
+//! Exhaustive checks of `Latest` under loom.
+//!
+//! Run with `RUSTFLAGS="--cfg loom" cargo test --test loom --release`.
+
+#![cfg(loom)]
+
+use loom::thread;
+use rio_thread_bench::channel;
+
+#[test]
+fn reader_never_sees_a_reclaimed_value_change() {
+    loom::model(|| {
+        // One slot for the published value, one the reader may be holding
+        // and one to write into, so `set` can never run out.
+        let (mut writer, reader) = channel::<usize>(3);
+        let handle = thread::spawn(move || {
+            let first = reader.get_arc().unwrap();
+            let value = *first;
+            let second = reader.get_arc().unwrap();
+            // Values only move forward, and a value the reader is holding is
+            // never reused for a later one.
+            assert!(*second >= value);
+            assert_eq!(*first, value);
+        });
+        writer.set(1);
+        writer.set(2);
+        handle.join().unwrap();
+
+        let reader = writer.reader();
+        assert_eq!(reader.get(), Some(2));
+        assert_eq!(reader.version(), Some(2));
+    });
+}
+
+#[test]
+fn wait_new_wakes_on_publish_or_close() {
+    loom::model(|| {
+        let (mut writer, mut reader) = channel::<usize>(2);
+        let handle = thread::spawn(move || reader.wait_new());
+        writer.set(1);
+        drop(writer);
+        let seen = handle.join().unwrap();
+        assert!(seen == Some(1) || seen.is_none());
+    });
+}
@@ -0,0 +1,12 @@
+use rio_thread_bench::channel;
+use rio_thread_bench::message::{LargeMessage, Payload};
+
+#[test]
+fn snapshot_round_trips_through_json() {
+    let (mut writer, reader) = channel::<LargeMessage>(2);
+    writer.set(LargeMessage::new(1., -1.));
+    let snapshot = reader.snapshot_serializable().unwrap();
+    let json = serde_json::to_string(&snapshot).unwrap();
+    let restored: LargeMessage = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored, snapshot);
+}